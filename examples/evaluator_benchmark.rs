@@ -0,0 +1,68 @@
+// Measures how many hands per second the evaluator handles.
+// Run with: cargo run --release --example evaluator_benchmark
+
+use std::time::{Duration, Instant};
+
+use ghaggs_joelsi_project::structs::card::Card;
+use ghaggs_joelsi_project::structs::card_set::CardSet;
+use ghaggs_joelsi_project::structs::deck::Deck;
+use ghaggs_joelsi_project::structs::evaluator::Evaluator;
+
+const HANDS: usize = 100_000;
+const ROUNDS: usize = 10;
+
+fn main() {
+    let start = Instant::now();
+    let evaluator = Evaluator::new();
+    println!("Built evaluator in {:?}", start.elapsed());
+
+    for size in 5..=7 {
+        let hands = random_hands(size);
+        let sets: Vec<CardSet> = hands.iter().map(|hand| CardSet::from(&hand[..])).collect();
+
+        let start = Instant::now();
+        let mut checksum: u64 = 0;
+        for _ in 0..ROUNDS {
+            for hand in &hands {
+                if let Some((rank, _)) = evaluator.evaluate(hand) {
+                    checksum += rank as u64;
+                }
+            }
+        }
+        report(&format!("{} cards, evaluate", size), start.elapsed(), checksum);
+
+        let start = Instant::now();
+        let mut checksum: u64 = 0;
+        for _ in 0..ROUNDS {
+            for set in &sets {
+                if let Some(rank) = evaluator.evaluate_set(*set) {
+                    checksum += rank as u64;
+                }
+            }
+        }
+        report(&format!("{} cards, evaluate_set", size), start.elapsed(), checksum);
+    }
+}
+
+fn report(name: &str, elapsed: Duration, checksum: u64) {
+    let evaluations = (HANDS * ROUNDS) as f64;
+    println!(
+        "{}: {:.0} evaluations/s ({:.1} ns each, checksum {})",
+        name,
+        evaluations / elapsed.as_secs_f64(),
+        elapsed.as_nanos() as f64 / evaluations,
+        checksum
+    );
+}
+
+// Deals 'HANDS' random hands of 'size' cards, each from a freshly shuffled deck
+fn random_hands(size: usize) -> Vec<Vec<Card>> {
+    let mut deck = Deck::new();
+    (0..HANDS)
+        .map(|_| {
+            deck.reset();
+            deck.shuffle();
+            deck.draw(size).expect("a full deck has enough cards")
+        })
+        .collect()
+}
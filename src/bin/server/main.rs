@@ -0,0 +1,486 @@
+// Authoritative poker server. Owns the Game, checks every action and tells the players what happens.
+// Run with: cargo run --bin server -- [address] [players] [chips] [grace period in seconds]
+// Defaults to 127.0.0.1:7878 with two players, 1000 chips each and a 30 second grace period.
+//
+// Clients talk to the server with the messages in structs::protocol, sent as length prefixed frames.
+// A player that loses their connection keeps their seat and stack, and can come back by sending the token from their Welcome.
+// When the grace period is over they check or fold whenever it's their turn, until they are back.
+// Every deck is shuffled from a seed the server commits to before dealing and the entropy every player sends back,
+// the seed is revealed after the hand, see structs::fairness.
+
+mod sessions;
+
+use std::collections::HashMap;
+use std::env;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ghaggs_joelsi_project::Game;
+use ghaggs_joelsi_project::structs::enums::Action;
+use ghaggs_joelsi_project::structs::error::ProtocolError;
+use ghaggs_joelsi_project::structs::fairness::{FairShuffle, MAX_ENTROPY_SIZE};
+use ghaggs_joelsi_project::structs::protocol::{check_version, read_message, write_message, ClientMessage, ServerMessage, PROTOCOL_VERSION};
+
+use sessions::{auto_action, Sessions};
+
+const SHOWDOWN_PAUSE: Duration = Duration::from_secs(3); // Time to look at the showdown before the next hand starts
+const ENTROPY_WAIT: Duration = Duration::from_secs(5); // How long the next hand waits for the players' entropy
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2); // A client that stops reading is disconnected after this, so it can't stall the game loop
+
+// Everything the connection threads tell the game loop. Connections are told apart by an id, a seat can have several over time
+enum Event {
+    Connected(TcpStream),
+    Message(usize, ClientMessage),
+    Disconnected(usize),
+}
+
+struct Server {
+    game: Game,
+    sessions: Sessions,
+    streams: HashMap<usize, TcpStream>, // Open connections by id, seated or waiting for their Hello
+    next_connection: usize,
+    players: usize,
+    started: bool,
+    next_hand_at: Option<Instant>, // When the next hand starts after a showdown
+    shuffle: Option<FairShuffle>, // Shuffle of the next hand or the hand being played, revealed when it's over
+    entropy: Vec<Option<Vec<u8>>>, // Entropy each seat has sent for the next hand
+    deal_at: Option<Instant>, // When the next hand is dealt even if some players haven't sent entropy, None when not waiting for it
+    dealt_seats: Vec<usize>, // Seats that were dealt cards this hand, in the order they got them
+    events: Sender<Event>,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let address = args.get(1).cloned().unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let players: usize = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(2);
+    let chips: u32 = args.get(3).and_then(|arg| arg.parse().ok()).unwrap_or(1000);
+    let grace_period: u64 = args.get(4).and_then(|arg| arg.parse().ok()).unwrap_or(30);
+
+    let listener = TcpListener::bind(&address).expect("Could not bind the server address");
+    println!("Poker server listening on {} for {} players", address, players);
+
+    let (sender, receiver) = mpsc::channel();
+    let accept_sender = sender.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if accept_sender.send(Event::Connected(stream)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = Server::new(Game::new(players, chips), players, Duration::from_secs(grace_period), sender);
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(event) => server.handle(event),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if !server.tick(Instant::now()) {
+            break;
+        }
+    }
+}
+
+impl Server {
+    fn new(game: Game, players: usize, grace_period: Duration, events: Sender<Event>) -> Self {
+        Server {
+            game,
+            sessions: Sessions::new(players, grace_period),
+            streams: HashMap::new(),
+            next_connection: 0,
+            players,
+            started: false,
+            next_hand_at: None,
+            shuffle: None,
+            entropy: Vec::new(),
+            deal_at: None,
+            dealt_seats: Vec::new(),
+            events,
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Connected(stream) => self.connect(stream),
+            Event::Message(connection, message) => match self.sessions.seat_of(connection) {
+                Some(seat) => self.handle_message(seat, message),
+                None => self.greet(connection, message),
+            },
+            Event::Disconnected(connection) => self.disconnect(connection),
+        }
+    }
+
+    // Starts reading from a new connection, it gets a seat once it has sent Hello
+    fn connect(&mut self, stream: TcpStream) {
+        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+            return;
+        }
+        let Ok(mut reader_stream) = stream.try_clone() else {
+            return;
+        };
+        let connection = self.next_connection;
+        self.next_connection += 1;
+        self.streams.insert(connection, stream);
+
+        let events = self.events.clone();
+        thread::spawn(move || {
+            loop {
+                match read_message::<_, ClientMessage>(&mut reader_stream) {
+                    Ok(message) => {
+                        if events.send(Event::Message(connection, message)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(ProtocolError::InvalidMessage(error)) => println!("Invalid message from connection {}: {}", connection, error),
+                    Err(_) => break,
+                }
+            }
+            let _ = events.send(Event::Disconnected(connection));
+        });
+    }
+
+    // The first message has to be a Hello with our protocol version, and a token when coming back to a seat
+    fn greet(&mut self, connection: usize, message: ClientMessage) {
+        // A connection that has been replaced or has closed
+        if !self.streams.contains_key(&connection) {
+            return;
+        }
+        let seat = match message {
+            ClientMessage::Hello { version, token } => match check_version(version) {
+                Ok(()) => self.sessions.seat_for(token),
+                Err(error) => Err(error.to_string()),
+            },
+            _ => Err(ProtocolError::UnexpectedMessage("Hello").to_string()),
+        };
+
+        match seat {
+            Ok(seat) => self.take_seat(seat, connection),
+            Err(message) => {
+                if let Some(mut stream) = self.streams.remove(&connection) {
+                    let _ = write_message(&mut stream, &ServerMessage::Error { message });
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+            }
+        }
+    }
+
+    // Puts a connection in a seat. A player coming back gets everyone at the table and the game as it is now
+    fn take_seat(&mut self, seat: usize, connection: usize) {
+        if let Some(old_connection) = self.sessions.sit(seat, connection)
+            && let Some(old_stream) = self.streams.remove(&old_connection)
+        {
+            let _ = old_stream.shutdown(Shutdown::Both);
+        }
+
+        let token = self.sessions.seats[seat].token.clone();
+        self.send(seat, &ServerMessage::Welcome { version: PROTOCOL_VERSION, seat, token });
+        if !self.sessions.seats[seat].joined {
+            return;
+        }
+
+        println!("{} reconnected", self.sessions.seats[seat].name);
+        for other in 0..self.sessions.seats.len() {
+            if self.sessions.seats[other].joined {
+                let name = self.sessions.seats[other].name.clone();
+                self.send(seat, &ServerMessage::Seat { seat: other, name });
+            }
+        }
+        if let Some(shuffle) = &self.shuffle {
+            let commitment = shuffle.commitment();
+            self.send(seat, &ServerMessage::Commit { commitment });
+        }
+        if self.started && self.deal_at.is_none() && !self.game.hand_over() {
+            let cards = self.game.players[seat].hand.cards.clone();
+            self.send(seat, &ServerMessage::Deal { cards });
+        }
+        if self.started {
+            let view = self.game.view_for(seat);
+            self.send(seat, &ServerMessage::View(Box::new(view)));
+        }
+        self.broadcast(&ServerMessage::Reconnected { seat });
+    }
+
+    // A connection closed or a write to it failed. The seat is held for the player and the grace period starts
+    fn disconnect(&mut self, connection: usize) {
+        if let Some(stream) = self.streams.remove(&connection) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        let Some(seat) = self.sessions.leave(connection, Instant::now()) else {
+            return;
+        };
+        println!("{} disconnected", self.sessions.seats[seat].name);
+        self.broadcast(&ServerMessage::Left { seat });
+    }
+
+    fn handle_message(&mut self, seat: usize, message: ClientMessage) {
+        match message {
+            ClientMessage::Hello { .. } => self.send_error(seat, "Already greeted"),
+            ClientMessage::Join { name } => self.join(seat, name),
+            ClientMessage::Action(action) => self.act(seat, action),
+            ClientMessage::Entropy { entropy } => self.add_entropy(seat, entropy),
+            ClientMessage::Chat { message } => self.broadcast(&ServerMessage::Chat { seat, message }),
+            ClientMessage::Ping { id } => self.send(seat, &ServerMessage::Pong { id }),
+            ClientMessage::Pong { .. } => {}
+        }
+    }
+
+    fn join(&mut self, seat: usize, name: String) {
+        if !self.sessions.join(seat, &name) {
+            self.send_error(seat, "Already joined");
+            return;
+        }
+        println!("{} joined seat {}", self.sessions.seats[seat].name, seat);
+
+        // Tell the new player who is already seated, and everyone about the new player
+        for other in 0..self.sessions.seats.len() {
+            if other != seat && self.sessions.seats[other].joined {
+                let name = self.sessions.seats[other].name.clone();
+                self.send(seat, &ServerMessage::Seat { seat: other, name });
+            }
+        }
+        let name = self.sessions.seats[seat].name.clone();
+        self.broadcast(&ServerMessage::Seat { seat, name });
+    }
+
+    // Entropy for the next hand. Entropy that comes after the hand is dealt is ignored, e.g. from a player that reconnected
+    fn add_entropy(&mut self, seat: usize, entropy: Vec<u8>) {
+        if entropy.len() > MAX_ENTROPY_SIZE {
+            self.send_error(seat, &format!("Entropy can be at most {} bytes", MAX_ENTROPY_SIZE));
+            return;
+        }
+        if self.deal_at.is_some() && self.entropy[seat].is_none() {
+            self.entropy[seat] = Some(entropy);
+        }
+    }
+
+    // Applies an action for a player, the Game checks that it's their turn and that the action is allowed
+    fn act(&mut self, seat: usize, action: Action) {
+        self.act_at(seat, action, Instant::now());
+    }
+
+    fn act_at(&mut self, seat: usize, action: Action, now: Instant) {
+        if !self.started || self.deal_at.is_some() {
+            self.send_error(seat, "The hand hasn't been dealt yet");
+            return;
+        }
+        match self.game.apply_action(seat, action) {
+            Ok(()) => {
+                self.broadcast(&ServerMessage::Acted { seat, action });
+                self.progress(now);
+            }
+            Err(error) => self.send_error(seat, &error.to_string()),
+        }
+    }
+
+    // Runs everything that doesn't wait for a player: starting the game and new hands, and playing for disconnected players.
+    // Takes the time as a parameter so the timers can be tested. Returns false when the game is over
+    fn tick(&mut self, now: Instant) -> bool {
+        if !self.started {
+            if self.sessions.all_joined() {
+                self.started = true;
+                return self.commit_to_next_hand(now);
+            }
+            return true;
+        }
+
+        if let Some(next_hand_at) = self.next_hand_at {
+            if now >= next_hand_at {
+                self.next_hand_at = None;
+                return self.commit_to_next_hand(now);
+            }
+            return true;
+        }
+
+        // Players that have lost their connection can't send entropy, they aren't waited for
+        if let Some(deal_at) = self.deal_at {
+            let everyone_sent = (0..self.sessions.seats.len()).all(|seat| self.entropy[seat].is_some() || !self.sessions.is_connected(seat));
+            if everyone_sent || now >= deal_at {
+                return self.start_hand(now);
+            }
+            return true;
+        }
+
+        // A player that has been gone for longer than the grace period checks if they can, otherwise folds
+        let current = self.game.current_player;
+        if !self.game.hand_over() && self.sessions.grace_period_over(current, now) {
+            let action = auto_action(&self.game.legal_actions(current));
+            self.act_at(current, action, now);
+        }
+        true
+    }
+
+    // Commits to a seed for the next hand and asks every player for entropy, the hand is dealt from both once it has arrived.
+    // Returns false if the game is over
+    fn commit_to_next_hand(&mut self, now: Instant) -> bool {
+        if self.game.players.iter().filter(|player| player.chips.chips > 0).count() < 2 {
+            let winner = (0..self.players).find(|&i| self.game.players[i].chips.chips > 0);
+            self.broadcast(&ServerMessage::GameOver { winner });
+            println!("Game over");
+            return false;
+        }
+
+        let shuffle = FairShuffle::new();
+        self.broadcast(&ServerMessage::Commit { commitment: shuffle.commitment() });
+        self.shuffle = Some(shuffle);
+        self.entropy = vec![None; self.sessions.seats.len()];
+        self.deal_at = Some(now + ENTROPY_WAIT);
+        true
+    }
+
+    // Deals the next hand from the committed seed and the entropy the players sent, in seat order
+    fn start_hand(&mut self, now: Instant) -> bool {
+        self.deal_at = None;
+        let Some(mut shuffle) = self.shuffle.take() else {
+            return false;
+        };
+        for entropy in self.entropy.drain(..).flatten() {
+            shuffle.add_client_entropy(&entropy);
+        }
+        self.game.set_next_seed(shuffle.deck_seed());
+        match self.game.start_hand() {
+            Ok(()) => {
+                self.shuffle = Some(shuffle);
+                self.dealt_seats = (0..self.game.players.len()).filter(|&i| !self.game.players[i].hand.cards.is_empty()).collect();
+                for seat in 0..self.sessions.seats.len() {
+                    let cards = self.game.players[seat].hand.cards.clone();
+                    self.send(seat, &ServerMessage::Deal { cards });
+                }
+                self.progress(now);
+                true
+            }
+            Err(error) => {
+                println!("Start hand error: {}", error);
+                false
+            }
+        }
+    }
+
+    // Deals the next streets when betting rounds are over and sends every player their view of the game
+    fn progress(&mut self, now: Instant) {
+        while !self.game.hand_over() && self.game.betting_round_over() {
+            if let Err(error) = self.game.advance() {
+                println!("Advance error: {}", error);
+                return;
+            }
+        }
+
+        for seat in 0..self.sessions.seats.len() {
+            let view = self.game.view_for(seat);
+            self.send(seat, &ServerMessage::View(Box::new(view)));
+        }
+        if self.game.hand_over() {
+            let hands = self.game.shown_hands();
+            let winners = self.game.showdown_winners.clone();
+            self.broadcast(&ServerMessage::Showdown { hands, winners });
+            if let Some(shuffle) = self.shuffle.take() {
+                let dealt_seats = self.dealt_seats.clone();
+                self.broadcast(&ServerMessage::Reveal { reveal: shuffle.reveal(), dealt_seats });
+            }
+            self.next_hand_at = Some(now + SHOWDOWN_PAUSE);
+        }
+    }
+
+    // Sends a message to a seat's player if they are connected. A write that fails or times out disconnects the player right away
+    fn send(&mut self, seat: usize, message: &ServerMessage) {
+        if let Some(connection) = self.sessions.seats[seat].connection
+            && let Some(stream) = self.streams.get_mut(&connection)
+            && write_message(stream, message).is_err()
+        {
+            self.disconnect(connection);
+        }
+    }
+
+    fn send_error(&mut self, seat: usize, message: &str) {
+        self.send(seat, &ServerMessage::Error { message: message.to_string() });
+    }
+
+    // Sends a message to every connected player
+    fn broadcast(&mut self, message: &ServerMessage) {
+        for seat in 0..self.sessions.seats.len() {
+            self.send(seat, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ghaggs_joelsi_project::structs::enums::Street;
+
+    const GRACE: Duration = Duration::from_secs(30);
+
+    // A heads-up server with the first hand dealt. Both players are seated without sockets, seat 0 on connection 0 and seat 1 on connection 1
+    fn dealt_heads_up() -> Server {
+        let (sender, _receiver) = mpsc::channel();
+        let mut server = Server::new(Game::new(2, 1000), 2, GRACE, sender);
+        for connection in 0..2 {
+            let seat = server.sessions.seat_for(None).unwrap();
+            server.sessions.sit(seat, connection);
+            server.sessions.join(seat, "");
+        }
+
+        let now = Instant::now();
+        assert!(server.tick(now));
+        assert!(server.deal_at.is_some());
+        // Nobody sent entropy, the hand waits for it
+        assert!(server.tick(now));
+        assert!(server.game.players[0].hand.cards.is_empty());
+        assert!(server.tick(now + ENTROPY_WAIT));
+        assert!(server.deal_at.is_none());
+        assert_eq!(server.game.players[0].hand.cards.len(), 2);
+        server
+    }
+
+    #[test]
+    fn disconnected_player_facing_a_bet_folds_after_the_grace_period() {
+        let mut server = dealt_heads_up();
+        let current = server.game.current_player;
+        server.disconnect(current);
+
+        let left_at = server.sessions.seats[current].disconnected_at.unwrap();
+        assert!(server.tick(left_at + GRACE - Duration::from_secs(1)));
+        assert!(server.game.action_history.is_empty());
+        assert_eq!(server.game.current_player, current);
+
+        assert!(server.tick(left_at + GRACE));
+        assert_eq!(server.game.action_history.last().unwrap().action, Action::Fold);
+        assert!(server.game.players[current].is_folded);
+        assert!(server.game.hand_over());
+    }
+
+    #[test]
+    fn disconnected_player_that_can_check_checks_after_the_grace_period() {
+        let mut server = dealt_heads_up();
+        let caller = server.game.current_player;
+        server.act(caller, Action::Call);
+        let checker = server.game.current_player;
+        assert_ne!(checker, caller);
+        server.disconnect(checker);
+
+        let left_at = server.sessions.seats[checker].disconnected_at.unwrap();
+        assert!(server.tick(left_at + GRACE));
+        assert_eq!(server.game.action_history.last().unwrap().action, Action::Check);
+        assert!(!server.game.players[checker].is_folded);
+        assert_eq!(server.game.street, Street::Flop);
+    }
+
+    #[test]
+    fn reconnecting_with_the_token_stops_the_auto_actions() {
+        let mut server = dealt_heads_up();
+        let current = server.game.current_player;
+        server.disconnect(current);
+        let left_at = server.sessions.seats[current].disconnected_at.unwrap();
+
+        let token = server.sessions.seats[current].token.clone();
+        let seat = server.sessions.seat_for(Some(token)).unwrap();
+        assert_eq!(seat, current);
+        server.take_seat(seat, 7);
+
+        assert!(server.tick(left_at + GRACE));
+        assert!(server.game.action_history.is_empty());
+        assert_eq!(server.sessions.seat_of(7), Some(current));
+    }
+}
@@ -0,0 +1,204 @@
+// Who sits in which seat, the connection they use and how long they have been gone.
+// Knows nothing about sockets, the server keeps the streams by connection id, so the seat rules can be tested on their own.
+
+use std::time::{Duration, Instant};
+
+use ghaggs_joelsi_project::structs::enums::Action;
+use ghaggs_joelsi_project::structs::legal_actions::LegalActions;
+
+pub struct Seat {
+    pub name: String,
+    pub token: String, // Session token, gets the seat back after a lost connection
+    pub connection: Option<usize>, // Id of the connection using the seat, None while the player is disconnected
+    pub joined: bool, // Has sent Join
+    pub disconnected_at: Option<Instant>,
+}
+
+pub struct Sessions {
+    pub seats: Vec<Seat>,
+    players: usize,
+    grace_period: Duration, // How long a disconnected player's turns wait for them
+}
+
+// A random session token, hard to guess so nobody else can take over a seat
+fn new_token() -> String {
+    format!("{:016x}{:016x}", rand::random::<u64>(), rand::random::<u64>())
+}
+
+impl Sessions {
+    pub fn new(players: usize, grace_period: Duration) -> Self {
+        Sessions { seats: Vec::new(), players, grace_period }
+    }
+
+    // The seat a connection is using, None if it hasn't sent Hello yet or has been replaced
+    pub fn seat_of(&self, connection: usize) -> Option<usize> {
+        self.seats.iter().position(|seat| seat.connection == Some(connection))
+    }
+
+    /// Finds the seat for a client that has sent Hello.
+    ///
+    /// # Returns
+    /// - A Result<usize, String> where:
+    ///     - usize: the seat belonging to the token, or a free seat for a new client without a token.
+    ///       A seat left before its player joined is given to the next new client, with a new token.
+    ///     - String: why the client can't sit down, the token is unknown or the table is full.
+    pub fn seat_for(&mut self, token: Option<String>) -> Result<usize, String> {
+        if let Some(token) = token {
+            return self.seats.iter().position(|seat| seat.token == token).ok_or_else(|| "Unknown session token".to_string());
+        }
+
+        if let Some(seat) = self.seats.iter().position(|seat| seat.connection.is_none() && !seat.joined) {
+            self.seats[seat].token = new_token();
+            return Ok(seat);
+        }
+        if self.seats.len() >= self.players {
+            return Err("The table is full".to_string());
+        }
+        let seat = self.seats.len();
+        self.seats.push(Seat {
+            name: format!("Player {}", seat + 1),
+            token: new_token(),
+            connection: None,
+            joined: false,
+            disconnected_at: None,
+        });
+        Ok(seat)
+    }
+
+    // Puts a connection in a seat. Returns the connection it takes over from, e.g. one that hasn't noticed that it's gone yet
+    pub fn sit(&mut self, seat: usize, connection: usize) -> Option<usize> {
+        let old_connection = self.seats[seat].connection.replace(connection);
+        self.seats[seat].disconnected_at = None;
+        old_connection
+    }
+
+    // A connection closed. The seat is held for the player and the grace period starts. Returns the seat, None if it had none
+    pub fn leave(&mut self, connection: usize, now: Instant) -> Option<usize> {
+        let seat = self.seat_of(connection)?;
+        self.seats[seat].connection = None;
+        self.seats[seat].disconnected_at = Some(now);
+        Some(seat)
+    }
+
+    // Takes the seat at the table under a name, an empty name keeps the default one. Returns false if the seat already joined
+    pub fn join(&mut self, seat: usize, name: &str) -> bool {
+        if self.seats[seat].joined {
+            return false;
+        }
+        if !name.trim().is_empty() {
+            self.seats[seat].name = name.trim().to_string();
+        }
+        self.seats[seat].joined = true;
+        true
+    }
+
+    // True when every seat at the table is taken and has joined, the game can start
+    pub fn all_joined(&self) -> bool {
+        self.seats.len() == self.players && self.seats.iter().all(|seat| seat.joined)
+    }
+
+    pub fn is_connected(&self, seat: usize) -> bool {
+        self.seats[seat].connection.is_some()
+    }
+
+    // True if the player has been gone for longer than the grace period, their turns are played for them until they are back
+    pub fn grace_period_over(&self, seat: usize, now: Instant) -> bool {
+        !self.is_connected(seat)
+            && self.seats[seat].disconnected_at.is_some_and(|at| now.saturating_duration_since(at) >= self.grace_period)
+    }
+}
+
+// The action played for a player whose grace period is over, a check if they can and otherwise a fold
+pub fn auto_action(legal: &LegalActions) -> Action {
+    if legal.can_check { Action::Check } else { Action::Fold }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRACE: Duration = Duration::from_secs(30);
+
+    // Two players that have both connected and joined, on connections 0 and 1
+    fn full_table() -> Sessions {
+        let mut sessions = Sessions::new(2, GRACE);
+        for connection in 0..2 {
+            let seat = sessions.seat_for(None).unwrap();
+            assert_eq!(sessions.sit(seat, connection), None);
+            assert!(sessions.join(seat, ""));
+        }
+        sessions
+    }
+
+    #[test]
+    fn new_players_get_seats_until_the_table_is_full() {
+        let mut sessions = full_table();
+        assert!(sessions.all_joined());
+        assert_eq!(sessions.seats[1].name, "Player 2");
+        assert_ne!(sessions.seats[0].token, sessions.seats[1].token);
+        assert_eq!(sessions.seat_for(None), Err("The table is full".to_string()));
+        assert!(!sessions.join(0, "Again"));
+    }
+
+    #[test]
+    fn token_gets_the_same_seat_back() {
+        let mut sessions = full_table();
+        let now = Instant::now();
+        assert_eq!(sessions.leave(1, now), Some(1));
+        assert!(!sessions.is_connected(1));
+        assert_eq!(sessions.leave(1, now), None);
+
+        let token = sessions.seats[1].token.clone();
+        assert_eq!(sessions.seat_for(Some(token.clone())), Ok(1));
+        assert_eq!(sessions.sit(1, 5), None);
+        assert_eq!(sessions.seat_of(5), Some(1));
+        assert_eq!(sessions.seats[1].disconnected_at, None);
+
+        // The same token again takes over from the connection in the seat
+        assert_eq!(sessions.seat_for(Some(token)), Ok(1));
+        assert_eq!(sessions.sit(1, 6), Some(5));
+        assert_eq!(sessions.seat_of(5), None);
+    }
+
+    #[test]
+    fn unknown_token_is_rejected() {
+        let mut sessions = full_table();
+        assert_eq!(sessions.seat_for(Some("not a token".to_string())), Err("Unknown session token".to_string()));
+        assert_eq!(sessions.seat_for(Some(String::new())), Err("Unknown session token".to_string()));
+    }
+
+    #[test]
+    fn seat_left_before_joining_goes_to_the_next_player() {
+        let mut sessions = Sessions::new(2, GRACE);
+        let seat = sessions.seat_for(None).unwrap();
+        sessions.sit(seat, 0);
+        let old_token = sessions.seats[seat].token.clone();
+        sessions.leave(0, Instant::now());
+
+        assert_eq!(sessions.seat_for(None), Ok(seat));
+        assert_ne!(sessions.seats[seat].token, old_token);
+        assert_eq!(sessions.seat_for(Some(old_token)), Err("Unknown session token".to_string()));
+    }
+
+    #[test]
+    fn grace_period_runs_from_the_disconnect() {
+        let mut sessions = full_table();
+        let now = Instant::now();
+        assert!(!sessions.grace_period_over(0, now + GRACE));
+
+        sessions.leave(0, now);
+        assert!(!sessions.grace_period_over(0, now));
+        assert!(!sessions.grace_period_over(0, now + GRACE - Duration::from_secs(1)));
+        assert!(sessions.grace_period_over(0, now + GRACE));
+
+        // Coming back stops it
+        sessions.sit(0, 2);
+        assert!(!sessions.grace_period_over(0, now + GRACE));
+    }
+
+    #[test]
+    fn auto_action_checks_when_it_can() {
+        assert_eq!(auto_action(&LegalActions { can_check: true, can_fold: true, ..LegalActions::default() }), Action::Check);
+        assert_eq!(auto_action(&LegalActions { can_call: true, can_fold: true, ..LegalActions::default() }), Action::Fold);
+    }
+}
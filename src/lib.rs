@@ -1,32 +1,48 @@
 pub mod structs;
 
-use poker_eval; // https://docs.rs/poker_eval/latest/poker_eval/
-
-use std::sync::Arc;
-
-use poker_eval::eval::five::{build_tables as build_tables_five, get_rank_five, TableFive};
-use poker_eval::eval::seven::{build_tables as build_tables_seven, get_rank as get_rank_seven, TableSeven};
-
+use structs::board::Board;
 use structs::deck::Deck;
-use structs::card::Card;
-use structs::player::{self, Player};
+use structs::enums::{Action, Street};
+use structs::error::ActionError;
+use structs::legal_actions::LegalActions;
+use structs::hand::HandStrength;
+use structs::player::Player;
 use structs::pot::Pot;
+use structs::view::{ActionRecord, PlayerView, ShowdownHand};
 
 ///// TODO: FUNKTION SOM JÄMFÖR ALLAS HÄNDER I GAME-STRUCTEN!!!
 
-pub type Board = Vec<Card>;
-
 pub struct Game {
     pub deck: Deck,
     pub players: Vec<Player>,
     pub board: Board,
-    pub t5: TableFive,
-    pub t7: Arc<TableSeven>,
     pub pot: Pot,
+    pub button: usize, // Index of the player with the dealer button
+    pub small_blind_seat: usize, // Index of the player that posted the small blind this hand
+    pub big_blind_seat: usize, // Index of the player that posted the big blind this hand
+    pub player_actions_done: Vec<bool>, // Players that have acted since the last full raise this round
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub hands_played: u32,
+    pub hand_seed: u64, // Seed the deck was shuffled with for the current hand, replaying it gives the same deal
+    pub next_seed: Option<u64>, // Seed to use for the next hand, a random one is picked if None
+    pub street: Street,
+    pub current_player: usize, // Index of the player whose turn it is
+    pub last_raiser_index: Option<usize>,
+    pub showdown_winners: Vec<usize>, // Everyone that won chips when the last hand ended
+    pub showdown_hands: Vec<Option<HandStrength>>, // Every player's hand at the end of the last hand, None for folded players
+    pub action_history: Vec<ActionRecord>, // Every action taken this hand, in order
 }
 
 impl Game {
+    // Create a game with 5/10 blinds and no ante
     pub fn new(players: usize, initial_chips: u32) -> Self {
+        Game::with_blinds(players, initial_chips, 5, 10, 0)
+    }
+
+    // Create a game with the given forced bets, they are posted automatically when a hand starts
+    pub fn with_blinds(players: usize, initial_chips: u32, small_blind: u32, big_blind: u32, ante: u32) -> Self {
         let mut player_list: Vec<Player> = Vec::new();
         for _ in 0..players {
             player_list.push(Player::new(initial_chips)); // Each player starts with initial chips
@@ -34,67 +50,592 @@ impl Game {
 
         let pot = Pot::new(players); // Initialise a pot with number of players
 
-        Game { deck: Deck::new(), players: player_list, board: Vec::new(), t5: build_tables_five(false), t7: build_tables_seven(false), pot }
+        Game {
+            deck: Deck::new(),
+            players: player_list,
+            board: Board::new(),
+            pot,
+            button: 0,
+            small_blind_seat: 0,
+            big_blind_seat: 0,
+            player_actions_done: vec![false; players],
+            small_blind,
+            big_blind,
+            ante,
+            hands_played: 0,
+            hand_seed: 0,
+            next_seed: None,
+            street: Street::Preflop,
+            current_player: 0,
+            last_raiser_index: None,
+            showdown_winners: Vec::new(),
+            showdown_hands: Vec::new(),
+            action_history: Vec::new(),
+        }
     }
 
-    // Handling playeraction: betting
-    pub fn bet(&mut self, player_index: usize, amount: u32) -> Result<(), &'static str> {
-        let player = &mut self.players[player_index];
-        // Check if player has enough chips
-        if player.chips.chips < amount {
-            return Err("Player doesn't have enough chips");
+    // Makes the next hand shuffle from the given seed, e.g. to replay a hand from a bug report
+    pub fn set_next_seed(&mut self, seed: u64) {
+        self.next_seed = Some(seed);
+    }
+
+    /// Starts a new hand: moves the button, shuffles, deals two cards to every player and posts antes and blinds.
+    /// 
+    /// # Behavior
+    /// - Players without chips sit out the hand, they are marked as folded.
+    /// - The button moves to the next player with chips, except for the first hand where it stays where it is.
+    /// - The small blind is posted by the player left of the button and the big blind by the player after that.
+    ///   Heads-up, the button posts the small blind instead and acts first preflop.
+    /// - A player that can't cover an ante or blind posts what they have and is all in.
+    /// - The deck is shuffled from next_seed if one was set, otherwise from a random seed. The seed is kept in hand_seed.
+    /// - Returns an error if less than two players have chips.
+    pub fn start_hand(&mut self) -> Result<(), ActionError> {
+        if self.players.iter().filter(|player| player.chips.chips > 0).count() < 2 {
+            return Err(ActionError::NotEnoughPlayers);
+        }
+
+        // Clear everything from the last hand
+        self.pot.reset();
+        self.board.cards.clear();
+        self.street = Street::Preflop;
+        self.last_raiser_index = None;
+        self.showdown_winners.clear();
+        self.showdown_hands.clear();
+        self.action_history.clear();
+        self.player_actions_done = vec![false; self.players.len()];
+        for player in &mut self.players {
+            player.is_folded = player.chips.chips == 0;
+            player.is_all_in = false;
+            player.hand.cards.clear();
+        }
+
+        // Move the button to a player that is in the hand
+        if self.hands_played > 0 || self.players[self.button].is_folded {
+            self.button = self.next_player_in_hand(self.button);
+        }
+        self.hands_played += 1;
+
+        self.hand_seed = self.next_seed.take().unwrap_or_else(rand::random);
+        self.deck.reset();
+        self.deck.shuffle_seeded(self.hand_seed);
+        for i in 0..self.players.len() {
+            if self.players[i].is_folded {
+                continue;
+            }
+            self.players[i].hand.cards = self.deck.draw(2)?;
+        }
+
+        // Antes are dead money, they go into the pot without counting towards the bet to call
+        if self.ante > 0 {
+            for i in 0..self.players.len() {
+                if self.players[i].is_folded {
+                    continue;
+                }
+                let ante = self.ante.min(self.players[i].chips.chips);
+                self.players[i].chips.deduct(ante);
+                if self.players[i].chips.chips == 0 {
+                    self.players[i].is_all_in = true;
+                }
+                self.pot.add_ante(i, ante);
+            }
+        }
+
+        // The blind seats are kept for the whole hand, players folding later doesn't move them
+        self.small_blind_seat = self.find_small_blind_seat();
+        self.big_blind_seat = self.next_player_in_hand(self.small_blind_seat);
+        let small_blind = self.small_blind.min(self.players[self.small_blind_seat].chips.chips);
+        self.put_in_chips(self.small_blind_seat, small_blind);
+        let big_blind = self.big_blind.min(self.players[self.big_blind_seat].chips.chips);
+        self.put_in_chips(self.big_blind_seat, big_blind);
+
+        // Everyone has to call the full big blind, even if the big blind player couldn't cover it
+        self.pot.current_bet = self.pot.current_bet.max(self.big_blind);
+        self.pot.last_raise = self.big_blind;
+
+        self.current_player = self.first_player_able_to_act(self.first_to_act(true));
+
+        Ok(())
+    }
+
+    /// Performs an action for the player whose turn it is and passes the turn on.
+    /// 
+    /// # Parameters
+    /// - 'player_index': index in Game.players of the acting player.
+    /// - 'action': the action to perform, see the bet, raise_to, call, check, fold and all_in functions for the rules of each.
+    /// 
+    /// # Behavior
+    /// - Returns an error if the hand or betting round is over, if it isn't the player's turn, if the player has folded or is all in,
+    ///   or if the action isn't allowed. Nothing changes on an error.
+    /// - The turn goes to the next player that hasn't folded or gone all in.
+    /// - Doesn't deal any cards, call advance() once betting_round_over() is true.
+    /// 
+    /// # Example
+    /// '''
+    /// game.apply_action(game.current_player, Action::Call)?;
+    /// if game.betting_round_over() {
+    ///     game.advance()?;
+    /// }
+    /// '''
+    pub fn apply_action(&mut self, player_index: usize, action: Action) -> Result<(), ActionError> {
+        if self.street == Street::Showdown {
+            return Err(ActionError::HandOver);
+        }
+        if player_index != self.current_player {
+            return Err(ActionError::NotYourTurn);
+        }
+        // The turn stays with the last player when nobody can act, e.g. after an all in is called
+        self.ensure_can_act(player_index)?;
+        if self.betting_round_over() {
+            return Err(ActionError::BettingRoundOver);
+        }
+
+        let bet_before = self.pot.current_bet;
+        match action {
+            Action::Fold => self.fold(player_index)?,
+            Action::Check => self.check(player_index)?,
+            Action::Call => self.call(player_index)?,
+            Action::Bet(amount) => self.bet(player_index, amount)?,
+            Action::RaiseTo(total) => self.raise_to(player_index, total)?,
+            Action::AllIn => self.all_in(player_index)?,
+        }
+        if self.pot.current_bet > bet_before {
+            self.last_raiser_index = Some(player_index);
+        }
+        self.action_history.push(ActionRecord { player: player_index, street: self.street, action });
+
+        if let Some(next_player) = self.next_player_able_to_act(player_index) {
+            self.current_player = next_player;
+        }
+        Ok(())
+    }
+
+    /// Checks if the current betting round is closed.
+    /// 
+    /// # Returns
+    /// - true if only one player is left in the hand, or if everyone that can still act has acted and matched the bet.
+    ///   A single player that can act doesn't have to act if they already match the bet, there is nobody left to bet against.
+    pub fn betting_round_over(&self) -> bool {
+        if self.street == Street::Showdown || self.players_in_hand() <= 1 {
+            return true;
+        }
+
+        let able_to_act: Vec<usize> = (0..self.players.len())
+            .filter(|&i| self.can_act(i))
+            .collect();
+        if able_to_act.len() <= 1 && self.non_folded_players_match_bet() {
+            return true;
+        }
+        able_to_act.iter().all(|&i| self.player_actions_done[i]) && self.non_folded_players_match_bet()
+    }
+
+    /// Moves the hand on to the next street once the betting round is over.
+    /// 
+    /// # Returns
+    /// - A Result<Street, ActionError> where:
+    ///     - Street: the street the hand is on now.
+    ///     - ActionError: BettingRoundNotOver or HandOver, or DeckExhausted if there are no cards left to deal.
+    /// 
+    /// # Behavior
+    /// - Preflop deals the flop, flop deals the turn and turn deals the river. The first player left of the button acts first.
+    /// - After the river, or when everyone but one player has folded, the pots are awarded and the street becomes Showdown.
+    /// - When everyone left is all in the betting rounds are over right away, call advance() until Showdown to run out the board.
+    pub fn advance(&mut self) -> Result<Street, ActionError> {
+        if self.street == Street::Showdown {
+            return Err(ActionError::HandOver);
+        }
+        if !self.betting_round_over() {
+            return Err(ActionError::BettingRoundNotOver);
+        }
+
+        if self.players_in_hand() <= 1 {
+            self.end_hand();
+            return Ok(self.street);
+        }
+
+        let (next_street, cards_to_deal) = match self.street {
+            Street::Preflop => (Street::Flop, 3),
+            Street::Flop => (Street::Turn, 1),
+            Street::Turn => (Street::River, 1),
+            Street::River | Street::Showdown => {
+                self.end_hand();
+                return Ok(self.street);
+            }
+        };
+
+        let cards = self.deck.draw(cards_to_deal)?;
+        self.board.cards.extend(cards);
+        self.reset_round();
+        self.street = next_street;
+        self.last_raiser_index = None;
+        self.current_player = self.first_player_able_to_act(self.first_to_act(false));
+
+        Ok(self.street)
+    }
+
+    /// Returns which actions a player is allowed to take right now.
+    /// 
+    /// # Parameters
+    /// - 'player_index': index in Game.players of the player to check.
+    /// 
+    /// # Returns
+    /// - LegalActions with every action the player can take, how much a call costs and the range a bet or raise can be in.
+    ///   Everything is false if it isn't the player's turn, the hand is over or the player has folded or is all in.
+    /// 
+    /// # Example
+    /// '''
+    /// let legal = game.legal_actions(game.current_player);
+    /// if legal.can_raise {
+    ///     game.apply_action(game.current_player, Action::RaiseTo(legal.min_raise_to))?;
+    /// }
+    /// '''
+    pub fn legal_actions(&self, player_index: usize) -> LegalActions {
+        if self.street == Street::Showdown || player_index != self.current_player || !self.can_act(player_index) {
+            return LegalActions::default();
         }
 
+        let chips = self.players[player_index].chips.chips;
+        let player_bet = self.pot.player_bets[player_index];
+        let to_call = self.pot.current_bet.saturating_sub(player_bet);
+        let action_open = !self.player_actions_done[player_index];
+
+        // Raising needs chips left after calling, and the action has to be open for the player
+        let can_raise_at_all = action_open && chips > to_call;
+        let max_raise_to = player_bet + chips;
+        let min_raise_to = (self.pot.current_bet + self.min_raise()).min(max_raise_to);
+
+        LegalActions {
+            can_fold: true,
+            can_check: to_call == 0,
+            can_call: to_call > 0 && chips >= to_call,
+            call_amount: to_call.min(chips),
+            can_bet: can_raise_at_all && self.pot.current_bet == 0,
+            can_raise: can_raise_at_all && self.pot.current_bet > 0,
+            min_raise_to: if can_raise_at_all { min_raise_to } else { 0 },
+            max_raise_to: if can_raise_at_all { max_raise_to } else { 0 },
+            can_all_in: chips > 0 && (action_open || chips <= to_call),
+        }
+    }
+
+    // True when the hand is over and the pots have been awarded
+    pub fn hand_over(&self) -> bool {
+        self.street == Street::Showdown
+    }
+
+    // True if a player's hole cards are shown to everyone, which happens at a showdown between two or more players that didn't fold
+    pub fn shows_cards(&self, player_index: usize) -> bool {
+        self.hand_over() && !self.players[player_index].is_folded && self.players_in_hand() >= 2
+    }
+
+    /// Returns what a player is allowed to see of the game, e.g. to send to them over the network.
+    ///
+    /// # Parameters
+    /// - 'player_index': index in Game.players of the viewing player.
+    ///
+    /// # Returns
+    /// - PlayerView with the viewing player's own hole cards, the board, stacks, pot and the actions taken this hand.
+    ///   Other players' hole cards are only included once they are shown at a showdown, see shows_cards.
+    ///
+    /// # Example
+    /// '''
+    /// let view = game.view_for(1);
+    /// println!("Your cards: {:?}", view.hole_cards());
+    /// '''
+    pub fn view_for(&self, player_index: usize) -> PlayerView {
+        let players = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, player)| {
+                let mut player = player.clone();
+                if i != player_index && !self.shows_cards(i) {
+                    player.hand.cards.clear();
+                }
+                player
+            })
+            .collect();
+        let showdown_hands = self
+            .showdown_hands
+            .iter()
+            .enumerate()
+            .map(|(i, strength)| if i == player_index || self.shows_cards(i) { strength.clone() } else { None })
+            .collect();
+
+        PlayerView {
+            seat: player_index,
+            street: self.street,
+            button: self.button,
+            current_player: if self.hand_over() { None } else { Some(self.current_player) },
+            board: self.board.clone(),
+            players,
+            pot: self.pot.clone(),
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            ante: self.ante,
+            legal_actions: self.legal_actions(player_index),
+            action_history: self.action_history.clone(),
+            showdown_winners: self.showdown_winners.clone(),
+            showdown_hands,
+        }
+    }
+
+    // The hands shown at the end of the hand, see shows_cards. Empty if the hand isn't over or everyone else folded
+    pub fn shown_hands(&self) -> Vec<ShowdownHand> {
+        (0..self.players.len())
+            .filter(|&i| self.shows_cards(i))
+            .filter_map(|i| {
+                let strength = self.showdown_hands.get(i)?.clone()?;
+                Some(ShowdownHand { seat: i, hand: self.players[i].hand.clone(), strength })
+            })
+            .collect()
+    }
+
+    // Award the pots and end the hand
+    fn end_hand(&mut self) {
+        self.showdown_hands = (0..self.players.len())
+            .map(|i| if self.players[i].is_folded { None } else { self.hand_strength(i) })
+            .collect();
+        self.showdown_winners = self.award_pots();
+        self.street = Street::Showdown;
+    }
+
+    // A player can act if they are still in the hand and have chips left to bet with
+    fn can_act(&self, player_index: usize) -> bool {
+        let player = &self.players[player_index];
+        !player.is_folded && !player.is_all_in
+    }
+
+    // The given player if they can act, otherwise the next player after them that can
+    fn first_player_able_to_act(&self, from: usize) -> usize {
+        if self.can_act(from) {
+            return from;
+        }
+        self.next_player_able_to_act(from).unwrap_or(from)
+    }
+
+    // Index of the next player after 'from' that can act, None if nobody can
+    fn next_player_able_to_act(&self, from: usize) -> Option<usize> {
+        let players = self.players.len();
+        (1..=players)
+            .map(|offset| (from + offset) % players)
+            .find(|&i| self.can_act(i))
+    }
+
+    // Index of the player that posts the small blind when a hand starts, heads-up that's the button. The big blind is the player after them
+    fn find_small_blind_seat(&self) -> usize {
+        if self.players_in_hand() == 2 {
+            self.button
+        }
+        else {
+            self.next_player_in_hand(self.button)
+        }
+    }
+
+    /// Returns the index of the player that acts first in a betting round.
+    /// 
+    /// # Parameters
+    /// - 'preflop': true for the first betting round, false for flop, turn and river.
+    /// 
+    /// # Behavior
+    /// - Preflop the player after the big blind acts first, heads-up that is the button.
+    /// - After the flop the first player left of the button acts first, heads-up that is the big blind.
+    pub fn first_to_act(&self, preflop: bool) -> usize {
+        if preflop {
+            self.next_player_in_hand(self.big_blind_seat)
+        }
+        else {
+            self.next_player_in_hand(self.button)
+        }
+    }
+
+    // Amount of players dealt into the current hand
+    fn players_in_hand(&self) -> usize {
+        self.players.iter().filter(|player| !player.is_folded).count()
+    }
+
+    // Index of the next player after 'from' (going left around the table) that hasn't folded or sat out
+    fn next_player_in_hand(&self, from: usize) -> usize {
+        let players = self.players.len();
+        (1..=players)
+            .map(|offset| (from + offset) % players)
+            .find(|&i| !self.players[i].is_folded)
+            .unwrap_or(from)
+    }
+
+    /// Handling playeraction: betting. Opens the betting in a round where nobody has bet yet.
+    /// 
+    /// # Parameters
+    /// - 'player_index': index in Game.players of the betting player.
+    /// - 'amount': the size of the bet.
+    /// 
+    /// # Behavior
+    /// - Returns an error if there already is a bet to call, use raise_to or raise_by instead.
+    /// - The bet must be at least the minimum raise, unless it puts the player all in.
+    pub fn bet(&mut self, player_index: usize, amount: u32) -> Result<(), ActionError> {
+        if self.pot.current_bet > 0 {
+            return Err(ActionError::BetAlreadyMade);
+        }
+        self.raise_to(player_index, amount)
+    }
+
+    /// Handling playeraction: raising. Raises the current bet to a total amount.
+    /// 
+    /// # Parameters
+    /// - 'player_index': index in Game.players of the raising player.
+    /// - 'total': what the player's total bet this round will be after the raise, NOT the amount added on top of the current bet.
+    /// 
+    /// # Behavior
+    /// - The raise increment (total - current bet) must be at least the minimum raise, which is the last full raise this round.
+    /// - A raise that puts the player all in is allowed below the minimum raise. Such an incomplete raise has to be called,
+    ///   but doesn't reopen the action for players that already have acted, they can only call or fold.
+    /// - A full raise reopens the action, every other player has to act again.
+    pub fn raise_to(&mut self, player_index: usize, total: u32) -> Result<(), ActionError> {
+        self.ensure_can_act(player_index)?;
+        let player = &self.players[player_index];
+
         let current_bet = self.pot.current_bet;
+        let player_bet = self.pot.player_bets[player_index];
+        if total <= current_bet {
+            return Err(ActionError::BetBelowCurrent { current_bet });
+        }
 
-        // If player tries to bet under the current bet
-        if amount < current_bet {
-            return Err("Bet must be at least the the current bet(or use call)");
+        // Check if player has enough chips
+        let amount = total - player_bet;
+        if player.chips.chips < amount {
+            return Err(ActionError::InsufficientChips { needed: amount, available: player.chips.chips });
         }
 
-        // Deduct the amount of chips from the betted player and add it to the pot
-        player.chips.deduct(amount);
-        self.pot.add_constribution(player_index, amount);
+        let is_all_in = player.chips.chips == amount;
+        let is_full_raise = total - current_bet >= self.min_raise();
+        if !is_full_raise && !is_all_in {
+            return Err(ActionError::RaiseBelowMinimum { min_raise: self.min_raise() });
+        }
+        if self.player_actions_done[player_index] {
+            return Err(ActionError::ActionNotReopened);
+        }
+
+        self.put_in_chips(player_index, amount);
+        self.register_raise(player_index, current_bet, is_full_raise);
 
         Ok(())
     }
 
+    /// Handling playeraction: raising. Raises the current bet by an amount.
+    /// 
+    /// # Parameters
+    /// - 'player_index': index in Game.players of the raising player.
+    /// - 'amount': how much the current bet is raised by. Same rules as raise_to.
+    pub fn raise_by(&mut self, player_index: usize, amount: u32) -> Result<(), ActionError> {
+        self.raise_to(player_index, self.pot.current_bet + amount)
+    }
+
+    // The smallest raise increment allowed right now, the size of the last full bet or raise this round but never below the big blind
+    pub fn min_raise(&self) -> u32 {
+        self.pot.last_raise.max(self.big_blind).max(1)
+    }
+
     // Handling playeraction: calling
-    pub fn call(&mut self, player_index: usize) -> Result<(), &'static str> {
-        let player = &mut self.players[player_index];
+    pub fn call(&mut self, player_index: usize) -> Result<(), ActionError> {
+        self.ensure_can_act(player_index)?;
+        let player = &self.players[player_index];
         let to_call = self.pot.current_bet.saturating_sub(self.pot.player_bets[player_index]);
 
         // Incase a player can't call because of lack of chips. Use all in instead
         if player.chips.chips < to_call {
-            return Err("Not enough chips to call");
+            return Err(ActionError::InsufficientChips { needed: to_call, available: player.chips.chips });
         }
 
         // Deduct the amount of chips from the calling player and add it to the pot
-        player.chips.deduct(to_call);
-        self.pot.add_constribution(player_index, to_call);
+        self.put_in_chips(player_index, to_call);
+        self.player_actions_done[player_index] = true;
 
         Ok(())
     }
 
+    // Handling playeraction: all in. The player puts every chip they have left into the pot
+    pub fn all_in(&mut self, player_index: usize) -> Result<(), ActionError> {
+        self.ensure_can_act(player_index)?;
+        let player = &self.players[player_index];
+
+        let amount = player.chips.chips;
+        if amount == 0 {
+            return Err(ActionError::NoChipsLeft);
+        }
+
+        // Going all in for more than the current bet is a raise, which is only allowed if the action is open for the player
+        let current_bet = self.pot.current_bet;
+        let total = self.pot.player_bets[player_index] + amount;
+        if total > current_bet && self.player_actions_done[player_index] {
+            return Err(ActionError::ActionNotReopened);
+        }
+
+        // Deduct every chip from the player and add them to the pot. Side pots are built from the contributions at showdown
+        self.put_in_chips(player_index, amount);
+        if total > current_bet {
+            let is_full_raise = total - current_bet >= self.min_raise();
+            self.register_raise(player_index, current_bet, is_full_raise);
+        }
+        else {
+            self.player_actions_done[player_index] = true;
+        }
+
+        Ok(())
+    }
+
+    // Move chips from a player to the pot, a player that runs out of chips is all in
+    fn put_in_chips(&mut self, player_index: usize, amount: u32) {
+        let player = &mut self.players[player_index];
+        player.chips.deduct(amount);
+        if player.chips.chips == 0 {
+            player.is_all_in = true;
+        }
+        self.pot.add_constribution(player_index, amount);
+    }
+
+    // Keep track of the raise size. A full raise reopens the action so everyone else has to act again
+    fn register_raise(&mut self, player_index: usize, previous_bet: u32, is_full_raise: bool) {
+        if is_full_raise {
+            self.pot.last_raise = self.pot.current_bet - previous_bet;
+            self.player_actions_done = vec![false; self.players.len()];
+        }
+        self.player_actions_done[player_index] = true;
+    }
+
     // Handling playeraction: checking
-    pub fn check(&self, player_index: usize) -> Result<(), &'static str> {
+    pub fn check(&mut self, player_index: usize) -> Result<(), ActionError> {
+        self.ensure_can_act(player_index)?;
         // If a player has betted, checks are invalid
         if self.pot.current_bet > self.pot.player_bets[player_index] {
-            return Err("Cannot check: need to call, raise or fold");
+            return Err(ActionError::CannotCheck);
         }
+        self.player_actions_done[player_index] = true;
         Ok(())
     }
 
     // Handling playeraction: folding
-    pub fn fold(&mut self, player_index: usize) {
+    pub fn fold(&mut self, player_index: usize) -> Result<(), ActionError> {
+        self.ensure_can_act(player_index)?;
         self.players[player_index].is_folded = true;
+        self.player_actions_done[player_index] = true;
+        Ok(())
+    }
+
+    // Folded and all in players can't take any action
+    fn ensure_can_act(&self, player_index: usize) -> Result<(), ActionError> {
+        let player = &self.players[player_index];
+        if player.is_folded {
+            return Err(ActionError::PlayerFolded);
+        }
+        if player.is_all_in {
+            return Err(ActionError::PlayerAllIn);
+        }
+        Ok(())
     }
 
     // Check how many players that haven't folded, true or false.
     pub fn non_folded_players_match_bet(&self) -> bool {
         for (i, player) in self.players.iter().enumerate() {
-            // If player has folded, skip their turn
-            if player.is_folded {
+            // If player has folded, skip their turn. All in players can't put in more chips, so they don't have to match
+            if player.is_folded || player.is_all_in {
                 continue;
             }
             let player_bet = self.pot.player_bets[i];
@@ -105,42 +646,481 @@ impl Game {
         true
     }
 
-    // Award the pot to the winner of the round
-    pub fn award_pot_to_winner(&mut self) {
-        let winner_index = self.best_hand();
-        let winnings = self.pot.total;
-        self.players[winner_index].chips.add(winnings);
+    /// Awards the main pot and every side pot to the best hand among the players eligible for that pot.
+    /// 
+    /// # Returns
+    /// - Vec<usize>: the indices in Game.players of everyone that won chips from another player, in seat order.
+    ///   A bet nobody called goes back to the bettor, but that doesn't make them a winner.
+    pub fn award_pots(&mut self) -> Vec<usize> {
+        let folded: Vec<bool> = self.players.iter().map(|player| player.is_folded).collect();
+        let mut pot_winners: Vec<usize> = Vec::new();
+        let mut previous_level = 0;
+        for side_pot in self.pot.side_pots(&folded) {
+            if side_pot.eligible_players.is_empty() {
+                continue;
+            }
+            let winners = self.winners_among(&side_pot.eligible_players);
+            self.split_pot(side_pot.amount, &winners);
+
+            // A pot with one eligible player is only won if someone else put chips into it
+            let level = side_pot.eligible_players.iter().map(|&i| self.pot.contributions[i]).min().unwrap_or(previous_level);
+            if side_pot.eligible_players.len() > 1 || side_pot.amount > level - previous_level {
+                pot_winners.extend(winners);
+            }
+            previous_level = level;
+        }
         self.pot.reset();
+
+        pot_winners.sort_unstable();
+        pot_winners.dedup();
+        pot_winners
+    }
+
+    /// Divides a pot evenly between the winners of it.
+    /// 
+    /// # Parameters
+    /// - 'amount': the amount of chips in the pot.
+    /// - 'winners': indices in Game.players of the players that share the pot.
+    /// 
+    /// # Behavior
+    /// - Chips that can't be divided evenly (odd chips) are given out one at a time, starting with the first winner to the left of the button.
+    pub fn split_pot(&mut self, amount: u32, winners: &[usize]) {
+        if winners.is_empty() {
+            return;
+        }
+        let share = amount / winners.len() as u32;
+        let odd_chips = (amount % winners.len() as u32) as usize;
+
+        let mut winners_in_seat_order = winners.to_vec();
+        winners_in_seat_order.sort_by_key(|&i| self.seats_left_of_button(i));
+
+        for (n, &i) in winners_in_seat_order.iter().enumerate() {
+            let extra_chip = if n < odd_chips { 1 } else { 0 };
+            self.players[i].chips.add(share + extra_chip);
+        }
+    }
+
+    // How many seats to the left of the button a player sits, the player directly left of the button is 0
+    fn seats_left_of_button(&self, player_index: usize) -> usize {
+        let players = self.players.len();
+        (player_index + players - self.button - 1) % players
     }
 
     // Reset pot after a round
     pub fn reset_round(&mut self) {
         self.pot.reset_round();
+        self.player_actions_done = vec![false; self.players.len()];
     }
 
     // Reset the game after a player wins to be able to play again if wanted
     pub fn reset_game(&mut self) {
         self.pot.reset();
+        self.player_actions_done = vec![false; self.players.len()];
+        self.street = Street::Preflop;
+        self.last_raiser_index = None;
+        self.showdown_winners.clear();
+        self.showdown_hands.clear();
+        self.action_history.clear();
         for player in &mut self.players {
             player.chips.chips = 1000;
             player.is_folded = false;
+            player.is_all_in = false;
         }
     }
 
-    /// Evaluates all players hands and returns the index of the player with the winning hand.
+    // Evaluates a player's hand together with the board, including the best five cards. None if there are too few cards to make a hand
+    pub fn hand_strength(&self, player_index: usize) -> Option<HandStrength> {
+        self.players[player_index].hand.evaluate(&self.board.cards)
+    }
+
+    /// Evaluates all non-folded players hands and returns the indices of the players with the winning hand.
     /// 
     /// Returns:
-    ///  - usize: contains the index of the player with the best hand in Game.players.
-    pub fn best_hand(&self) -> usize {
-        let mut vec: Vec<(u32, usize)> = Vec::new();
-        for (i, player) in self.players.iter().enumerate() {
-            if player.is_folded {
+    ///  - Vec<usize>: contains the indices in Game.players of every player tied for the best hand, in seat order.
+    pub fn winners(&self) -> Vec<usize> {
+        let non_folded_players: Vec<usize> = (0..self.players.len())
+            .filter(|&i| !self.players[i].is_folded)
+            .collect();
+        self.winners_among(&non_folded_players)
+    }
+
+    /// Evaluates the hands of the given players and returns the indices of the ones with the winning hand.
+    /// 
+    /// # Parameters
+    /// - 'player_indices': indices in Game.players of the players competing, e.g. the eligible players of a side pot.
+    /// 
+    /// Returns:
+    ///  - Vec<usize>: contains the indices in Game.players of every player tied for the best hand, in seat order. Empty if no players were given.
+    pub fn winners_among(&self, player_indices: &[usize]) -> Vec<usize> {
+        let ranks: Vec<(u32, usize)> = player_indices
+            .iter()
+            .map(|&i| {
+                // A hand that can't be evaluated (no board yet, e.g. everyone else folded preflop) ranks lowest
+                let strength = self.hand_strength(i);
+                (strength.map(|strength| strength.rank).unwrap_or(0), i)
+            })
+            .collect();
+        let best_rank = match ranks.iter().map(|&(rank, _)| rank).max() {
+            Some(rank) => rank,
+            None => return Vec::new(),
+        };
+        let mut winners: Vec<usize> = ranks
+            .into_iter()
+            .filter(|&(rank, _)| rank == best_rank)
+            .map(|(_, i)| i)
+            .collect();
+        winners.sort_unstable();
+        winners
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::card::Card;
+    use crate::structs::enums::HandCategory;
+
+    // A game where the hand is over and the players have put in the given amounts with the given hands
+    fn game_at_showdown(hands: &[&str], board: &str, contributions: &[u32]) -> Game {
+        let mut game = Game::new(hands.len(), 0);
+        for (i, hand) in hands.iter().enumerate() {
+            game.players[i].hand = hand.parse().unwrap();
+            game.players[i].is_all_in = true;
+            game.pot.add_constribution(i, contributions[i]);
+        }
+        game.board = board.parse().unwrap();
+        game.street = Street::River;
+        game
+    }
+
+    #[test]
+    fn side_pot_winners_are_showdown_winners() {
+        let mut game = game_at_showdown(&["AhAs", "KdKc", "7h2s"], "3c4d9hJsQd", &[100, 300, 1000]);
+        game.advance().unwrap();
+
+        let chips: Vec<u32> = game.players.iter().map(|player| player.chips.chips).collect();
+        assert_eq!(chips, vec![300, 400, 700]);
+        assert_eq!(game.showdown_winners, vec![0, 1]); // Player 2 only gets their uncalled chips back
+    }
+
+    #[test]
+    fn fold_winner_is_showdown_winner() {
+        let mut game = game_at_showdown(&["AhAs", "KdKc"], "", &[10, 20]);
+        game.players[0].is_folded = true;
+        game.advance().unwrap();
+        assert_eq!(game.showdown_winners, vec![1]);
+        assert_eq!(game.players[1].chips.chips, 30);
+        assert!(game.shown_hands().is_empty());
+    }
+
+    #[test]
+    fn shown_hands_leave_out_folded_players() {
+        let mut game = game_at_showdown(&["AhAs", "KdKc", "7h2s"], "3c4d9hJsQd", &[100, 100, 100]);
+        assert!(game.shown_hands().is_empty());
+        game.players[2].is_folded = true;
+        game.advance().unwrap();
+
+        let shown = game.shown_hands();
+        let seats: Vec<usize> = shown.iter().map(|shown| shown.seat).collect();
+        assert_eq!(seats, vec![0, 1]);
+        assert_eq!(shown[0].hand.to_string(), "AhAs");
+        assert_eq!(shown[1].strength.category, HandCategory::OnePair);
+    }
+
+    #[test]
+    fn split_pot_winners() {
+        let mut game = game_at_showdown(&["AhKs", "AdKc", "7h2s"], "3c4d9hJsQd", &[100, 100, 100]);
+        game.advance().unwrap();
+        assert_eq!(game.showdown_winners, vec![0, 1]);
+        assert_eq!(game.players[0].chips.chips, 150);
+    }
+
+    #[test]
+    fn odd_chip_goes_to_the_first_winner_left_of_the_button() {
+        let mut game = Game::new(3, 0);
+        game.button = 1;
+        game.split_pot(101, &[0, 2]);
+        assert_eq!((game.players[0].chips.chips, game.players[2].chips.chips), (50, 51));
+
+        let mut game = Game::new(3, 0);
+        game.button = 2;
+        game.split_pot(101, &[0, 2]);
+        assert_eq!((game.players[0].chips.chips, game.players[2].chips.chips), (51, 50));
+
+        // Player 2 folded, the odd chip of the 105 chip pot goes to player 1 who sits left of the button
+        let mut game = game_at_showdown(&["AhKs", "AdKc", "7h2s"], "3c4d9hJsQd", &[35, 35, 35]);
+        game.players[2].is_folded = true;
+        game.advance().unwrap();
+        assert_eq!((game.players[0].chips.chips, game.players[1].chips.chips), (52, 53));
+    }
+
+    #[test]
+    fn three_way_split_with_two_odd_chips() {
+        let mut game = Game::new(4, 0);
+        game.button = 1;
+        game.split_pot(302, &[0, 1, 3]);
+        let chips: Vec<u32> = game.players.iter().map(|player| player.chips.chips).collect();
+        assert_eq!(chips, vec![101, 100, 0, 101]);
+    }
+
+    #[test]
+    fn no_actions_after_an_all_in_is_called() {
+        let mut game = Game::new(2, 1000);
+        game.start_hand().unwrap();
+        let first = game.current_player;
+        game.apply_action(first, Action::AllIn).unwrap();
+        let second = game.current_player;
+        game.apply_action(second, Action::Call).unwrap();
+
+        assert!(game.betting_round_over());
+        let current = game.current_player;
+        assert_eq!(game.apply_action(current, Action::Fold), Err(ActionError::PlayerAllIn));
+        assert_eq!(game.apply_action(current, Action::Check), Err(ActionError::PlayerAllIn));
+        assert!(!game.players[current].is_folded);
+        assert_eq!(game.action_history.len(), 2);
+    }
+
+    #[test]
+    fn no_actions_once_the_betting_round_is_over() {
+        let mut game = Game::new(2, 1000);
+        game.start_hand().unwrap();
+        let first = game.current_player;
+        game.apply_action(first, Action::Call).unwrap();
+        let second = game.current_player;
+        game.apply_action(second, Action::Check).unwrap();
+
+        assert!(game.betting_round_over());
+        let current = game.current_player;
+        assert_eq!(game.apply_action(current, Action::Check), Err(ActionError::BettingRoundOver));
+        assert_eq!(game.advance(), Ok(Street::Flop));
+    }
+
+    #[test]
+    fn folded_and_all_in_players_cannot_act() {
+        let mut game = Game::new(3, 1000);
+        game.start_hand().unwrap();
+        game.fold(0).unwrap();
+        game.players[1].is_all_in = true;
+        let chips = game.players[0].chips.chips;
+
+        assert_eq!(game.call(0), Err(ActionError::PlayerFolded));
+        assert_eq!(game.check(0), Err(ActionError::PlayerFolded));
+        assert_eq!(game.fold(0), Err(ActionError::PlayerFolded));
+        assert_eq!(game.raise_to(0, 100), Err(ActionError::PlayerFolded));
+        assert_eq!(game.all_in(0), Err(ActionError::PlayerFolded));
+        assert_eq!(game.call(1), Err(ActionError::PlayerAllIn));
+        assert_eq!(game.check(1), Err(ActionError::PlayerAllIn));
+        assert_eq!(game.fold(1), Err(ActionError::PlayerAllIn));
+        assert_eq!(game.players[0].chips.chips, chips);
+        assert!(!game.players[1].is_folded);
+    }
+
+    #[test]
+    fn blind_seats_stay_put_when_a_player_folds() {
+        let mut game = Game::new(3, 1000);
+        game.start_hand().unwrap();
+        assert_eq!((game.button, game.small_blind_seat, game.big_blind_seat), (0, 1, 2));
+        assert_eq!(game.current_player, 0);
+
+        game.apply_action(0, Action::Fold).unwrap();
+        assert_eq!((game.small_blind_seat, game.big_blind_seat), (1, 2));
+    }
+
+    // Checks or calls every street until the river is dealt, returns every player's hole cards and the board
+    fn deal_out(game: &mut Game) -> (Vec<Vec<Card>>, Vec<Card>) {
+        game.start_hand().unwrap();
+        while game.street != Street::River {
+            if game.betting_round_over() {
+                game.advance().unwrap();
                 continue;
             }
-            let (rank, _) = player.hand.evaluate(&self.board, &self.t5, &self.t7);
-            vec.push((rank, i));
+            let player = game.current_player;
+            let action = if game.legal_actions(player).can_check { Action::Check } else { Action::Call };
+            game.apply_action(player, action).unwrap();
+        }
+        let hole_cards = game.players.iter().map(|player| player.hand.cards.clone()).collect();
+        (hole_cards, game.board.cards.clone())
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_hand() {
+        let mut first = Game::new(4, 1000);
+        let mut second = Game::new(4, 1000);
+        first.set_next_seed(42);
+        second.set_next_seed(42);
+
+        let dealt = deal_out(&mut first);
+        assert_eq!(deal_out(&mut second), dealt);
+        assert_eq!((first.hand_seed, second.hand_seed), (42, 42));
+        assert_eq!(dealt.1.len(), 5);
+
+        let mut other = Game::new(4, 1000);
+        other.set_next_seed(43);
+        assert_ne!(deal_out(&mut other), dealt);
+    }
+
+    #[test]
+    fn hand_seed_replays_a_random_hand() {
+        let mut game = Game::new(3, 1000);
+        let dealt = deal_out(&mut game);
+        assert_eq!(game.next_seed, None);
+
+        let mut replay = Game::new(3, 1000);
+        replay.set_next_seed(game.hand_seed);
+        assert_eq!(deal_out(&mut replay), dealt);
+    }
+
+    // Four players with 5/10 blinds: the button is player 0, the blinds are players 1 and 2 and player 3 acts first
+    fn four_handed(chips: &[u32]) -> Game {
+        let mut game = Game::new(4, 1000);
+        for (i, &chips) in chips.iter().enumerate() {
+            game.players[i].chips.chips = chips;
+        }
+        game.start_hand().unwrap();
+        assert_eq!(game.current_player, 3);
+        game
+    }
+
+    #[test]
+    fn raise_has_to_be_at_least_the_last_increment() {
+        let mut game = four_handed(&[1000; 4]);
+        assert_eq!(game.min_raise(), 10);
+        assert_eq!(game.apply_action(3, Action::RaiseTo(15)), Err(ActionError::RaiseBelowMinimum { min_raise: 10 }));
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        assert_eq!(game.min_raise(), 20);
+
+        assert_eq!(game.apply_action(0, Action::RaiseTo(45)), Err(ActionError::RaiseBelowMinimum { min_raise: 20 }));
+        assert_eq!(game.current_player, 0);
+        game.apply_action(0, Action::RaiseTo(50)).unwrap();
+        assert_eq!(game.pot.current_bet, 50);
+    }
+
+    #[test]
+    fn raise_by_adds_to_the_current_bet() {
+        let mut game = four_handed(&[1000; 4]);
+        game.raise_by(3, 25).unwrap();
+        assert_eq!((game.pot.current_bet, game.pot.player_bets[3]), (35, 35));
+        assert_eq!(game.players[3].chips.chips, 965);
+        assert_eq!(game.min_raise(), 25);
+        assert_eq!(game.raise_by(0, 20), Err(ActionError::RaiseBelowMinimum { min_raise: 25 }));
+    }
+
+    #[test]
+    fn opening_bet() {
+        let mut game = four_handed(&[1000; 4]);
+        for player in [3, 0, 1] {
+            game.apply_action(player, Action::Call).unwrap();
+        }
+        game.apply_action(2, Action::Check).unwrap();
+        assert_eq!(game.advance(), Ok(Street::Flop));
+        assert_eq!((game.current_player, game.pot.current_bet), (1, 0));
+
+        assert_eq!(game.apply_action(1, Action::Bet(5)), Err(ActionError::RaiseBelowMinimum { min_raise: 10 }));
+        game.apply_action(1, Action::Bet(40)).unwrap();
+        assert_eq!((game.pot.current_bet, game.min_raise()), (40, 40));
+        assert_eq!(game.apply_action(2, Action::Bet(80)), Err(ActionError::BetAlreadyMade));
+        game.apply_action(2, Action::RaiseTo(80)).unwrap();
+    }
+
+    #[test]
+    fn short_all_in_does_not_reopen_the_action() {
+        let mut game = four_handed(&[40, 1000, 1000, 1000]);
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        game.apply_action(0, Action::AllIn).unwrap();
+        assert_eq!(game.pot.current_bet, 40);
+        assert_eq!(game.min_raise(), 20); // Raising by 10 wasn't a full raise
+
+        // Player 1 hasn't acted yet and may still raise
+        assert!(game.legal_actions(1).can_raise);
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        // Player 3 already acted and can only call or fold
+        assert_eq!(game.apply_action(3, Action::RaiseTo(100)), Err(ActionError::ActionNotReopened));
+        assert_eq!(game.apply_action(3, Action::AllIn), Err(ActionError::ActionNotReopened));
+        game.apply_action(3, Action::Call).unwrap();
+        assert!(game.betting_round_over());
+    }
+
+    #[test]
+    fn full_raise_after_a_short_all_in_resets_the_minimum_raise() {
+        let mut game = four_handed(&[40, 1000, 1000, 1000]);
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        game.apply_action(0, Action::AllIn).unwrap();
+
+        assert_eq!(game.apply_action(1, Action::RaiseTo(55)), Err(ActionError::RaiseBelowMinimum { min_raise: 20 }));
+        game.apply_action(1, Action::RaiseTo(100)).unwrap();
+        assert_eq!(game.min_raise(), 60);
+
+        // The full raise reopens the action for player 3
+        game.apply_action(2, Action::Fold).unwrap();
+        assert_eq!(game.apply_action(3, Action::RaiseTo(150)), Err(ActionError::RaiseBelowMinimum { min_raise: 60 }));
+        game.apply_action(3, Action::RaiseTo(160)).unwrap();
+    }
+
+    #[test]
+    fn check_or_call_depends_on_the_bet() {
+        let mut game = four_handed(&[1000; 4]);
+        let facing_blind = game.legal_actions(3);
+        assert!(!facing_blind.can_check && facing_blind.can_call && facing_blind.can_fold);
+        assert_eq!(facing_blind.call_amount, 10);
+        assert!(facing_blind.can_raise && !facing_blind.can_bet);
+        assert_eq!((facing_blind.min_raise_to, facing_blind.max_raise_to), (20, 1000));
+
+        for player in [3, 0, 1] {
+            game.apply_action(player, Action::Call).unwrap();
         }
-        vec.sort_by_key(|&(rank, _)| std::cmp::Reverse(rank));
-        vec[0].1
+        let big_blind = game.legal_actions(2);
+        assert!(big_blind.can_check && !big_blind.can_call);
+        assert_eq!(big_blind.call_amount, 0);
+
+        game.apply_action(2, Action::Check).unwrap();
+        game.advance().unwrap();
+        let first_on_flop = game.legal_actions(1);
+        assert!(first_on_flop.can_check && first_on_flop.can_bet && !first_on_flop.can_raise);
+        assert_eq!(first_on_flop.min_raise_to, 10);
+    }
+
+    #[test]
+    fn call_amount_is_capped_at_a_short_stack() {
+        let mut game = four_handed(&[25, 1000, 1000, 1000]);
+        game.apply_action(3, Action::RaiseTo(100)).unwrap();
+        let short = game.legal_actions(0);
+        assert!(!short.can_call && !short.can_raise && short.can_all_in);
+        assert_eq!(short.call_amount, 25);
+    }
+
+    #[test]
+    fn stack_below_the_minimum_raise_can_only_go_all_in() {
+        let mut game = four_handed(&[40, 1000, 1000, 1000]);
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        let short = game.legal_actions(0);
+        assert!(short.can_call && short.can_raise && short.can_all_in);
+        assert_eq!((short.min_raise_to, short.max_raise_to), (40, 40));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn no_raising_after_a_short_all_in_for_players_that_acted() {
+        let mut game = four_handed(&[40, 1000, 1000, 1000]);
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        game.apply_action(0, Action::AllIn).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        let legal = game.legal_actions(3);
+        assert!(legal.can_call && legal.can_fold);
+        assert!(!legal.can_raise && !legal.can_all_in);
+        assert_eq!((legal.call_amount, legal.min_raise_to, legal.max_raise_to), (10, 0, 0));
+    }
+
+    #[test]
+    fn nothing_is_legal_out_of_turn() {
+        let mut game = four_handed(&[1000; 4]);
+        assert_eq!(game.legal_actions(1), LegalActions::default());
+
+        game.apply_action(3, Action::Fold).unwrap();
+        assert_eq!(game.legal_actions(3), LegalActions::default());
+
+        game.current_player = 3; // Even if the turn were theirs, a folded player can't act
+        assert_eq!(game.legal_actions(3), LegalActions::default());
+    }
+}
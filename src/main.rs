@@ -1,37 +1,46 @@
-use std::{collections::HashMap, net, vec};
+use std::{collections::HashMap, env, net::TcpStream, sync::mpsc::{self, Receiver, Sender}, thread, time::Duration, vec};
 
 use ggez::{
-    event::{self, EventHandler}, glam::Vec2, graphics::{self, Color, DrawMode, DrawParam, Image, Mesh, Rect, Text, TextFragment}, input::{gamepad::gilrs::ev, keyboard::{KeyCode, KeyInput}, mouse::MouseButton}, Context, ContextBuilder, GameResult
+    event::{self, EventHandler}, glam::Vec2, graphics::{self, Color, DrawMode, DrawParam, Image, Rect, Text, TextFragment}, input::{keyboard::{KeyCode, KeyInput}, mouse::MouseButton}, Context, ContextBuilder, GameResult
 };
 
 use ghaggs_joelsi_project::{
     structs::{
-        card::Card, enums::{Rank, Suit}, player::{self, Player as BackendPlayer}
+        card::Card, card_set::CardSet, enums::{Action, Rank, Suit}, equity::{calculate_equity, EquitySettings, PlayerEquity},
+        error::ProtocolError, fairness::{seen_cards, verify, Commitment}, hand::Hand, legal_actions::LegalActions, player::Player as BackendPlayer,
+        protocol::{check_version, read_message, write_message, ClientMessage, ServerMessage, PROTOCOL_VERSION}, view::PlayerView
     }, Game
 };
 
 
+// Run with: cargo run, or cargo run -- --connect <address> [name] to play at a game server
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    let connection = match args.iter().position(|arg| arg == "--connect") {
+        Some(i) => {
+            let address = args.get(i + 1).map(String::as_str).unwrap_or("127.0.0.1:7878");
+            let name = args.get(i + 2).map(String::as_str).unwrap_or("Player");
+            match Connection::open(address, name) {
+                Ok(connection) => Some(connection),
+                Err(error) => {
+                    println!("Could not connect to {}: {}", address, error);
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
+
     // Make a Context.
     let (mut context, event_loop) = ContextBuilder::new("Poker", "Gustav, Joel")
         .add_resource_path("./resources")
         .build()
         .expect("Failed to create ggez context!");
 
-    let my_game = MyGame::new(&mut context);
+    let my_game = MyGame::new(&mut context, connection);
     event::run(context, event_loop, my_game);
 }
 
-// Texas Hold em
-#[derive(Clone, Copy, PartialEq)]
-enum GameState {
-    Preflop, 
-    Flop,
-    Turn, 
-    River,
-    Showdown,
-}
-
 #[derive(Clone, Copy, PartialEq, Debug)]
 enum PlayerActions {
     None,
@@ -39,6 +48,7 @@ enum PlayerActions {
     Check,
     Call,
     Fold,
+    AllIn,
 }
 
 // Frontend player representation
@@ -50,26 +60,147 @@ struct FrontendPlayer {
     position: Vec2,
     last_action: Option<PlayerActions>,
 }
+
+// What the network thread tells the game. When the connection is lost the thread keeps trying to get the seat back
+enum NetworkEvent {
+    Message(ServerMessage),
+    Disconnected(String), // The connection was lost, with the reason. Reconnecting in the background
+    Reconnected(TcpStream), // Back at the table, messages to the server are written to the new stream
+    Closed(String), // The server won't take us back, e.g. it was restarted and doesn't know the token
+}
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+// The connection to a game server when playing over the network
+struct Connection {
+    stream: TcpStream, // Messages to the server are written here, a background thread reads what the server sends
+    receiver: Receiver<NetworkEvent>,
+    seat: usize,
+    names: Vec<String>, // Names of the seated players, by seat
+    connected: bool,
+    commitment: Option<Commitment>, // Published by the server before the hand being played was dealt
+    entropy: Vec<u8>, // What we sent back for the hand, the shuffle has to have used it
+}
+
+/// Says hello to a game server and gets a seat.
+///
+/// # Parameters
+/// - 'token': the session token from an earlier Welcome to get the same seat back, None for a new player.
+///
+/// # Returns
+/// - A Result<(TcpStream, usize, String), ProtocolError> where:
+///     - (TcpStream, usize, String): the connection, the seat and the session token to reconnect with.
+///     - ProtocolError: if the server can't be reached, uses another protocol version or refuses the connection (e.g. the table is full).
+fn handshake(address: &str, token: Option<String>) -> Result<(TcpStream, usize, String), ProtocolError> {
+    let mut stream = TcpStream::connect(address)?;
+    write_message(&mut stream, &ClientMessage::Hello { version: PROTOCOL_VERSION, token })?;
+    match read_message(&mut stream)? {
+        ServerMessage::Welcome { version, seat, token } => {
+            check_version(version)?;
+            Ok((stream, seat, token))
+        }
+        ServerMessage::Error { message } => Err(ProtocolError::Rejected(message)),
+        _ => Err(ProtocolError::UnexpectedMessage("Welcome")),
+    }
+}
+
+// Reads the server's messages. A lost connection is reconnected with the session token, until the game is over
+fn run_network(mut reader: TcpStream, address: String, token: String, sender: Sender<NetworkEvent>) {
+    let mut game_over = false;
+    loop {
+        let reason = loop {
+            match read_message(&mut reader) {
+                Ok(message) => {
+                    game_over |= matches!(message, ServerMessage::GameOver { .. });
+                    if sender.send(NetworkEvent::Message(message)).is_err() {
+                        return;
+                    }
+                }
+                Err(ProtocolError::InvalidMessage(error)) => println!("Invalid message from the server: {}", error),
+                Err(error) => break error.to_string(),
+            }
+        };
+        // The server closes every connection when the game is over, there is nothing to come back to
+        if game_over || sender.send(NetworkEvent::Disconnected(reason)).is_err() {
+            return;
+        }
+
+        loop {
+            thread::sleep(RECONNECT_INTERVAL);
+            match handshake(&address, Some(token.clone())) {
+                Ok((stream, _, _)) => {
+                    let Ok(writer) = stream.try_clone() else {
+                        continue;
+                    };
+                    reader = stream;
+                    if sender.send(NetworkEvent::Reconnected(writer)).is_err() {
+                        return;
+                    }
+                    break;
+                }
+                Err(ProtocolError::Rejected(message)) => {
+                    let _ = sender.send(NetworkEvent::Closed(message));
+                    return;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+impl Connection {
+    // Connects to a game server and joins the table, the server's messages arrive on 'receiver'
+    fn open(address: &str, name: &str) -> Result<Connection, ProtocolError> {
+        let (mut stream, seat, token) = handshake(address, None)?;
+        write_message(&mut stream, &ClientMessage::Join { name: name.to_string() })?;
+
+        let reader = stream.try_clone()?;
+        let address = address.to_string();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || run_network(reader, address, token, sender));
+
+        Ok(Connection { stream, receiver, seat, names: Vec::new(), connected: true, commitment: None, entropy: Vec::new() })
+    }
+
+    fn send(&mut self, message: &ClientMessage) {
+        if self.connected && write_message(&mut self.stream, message).is_err() {
+            self.connected = false;
+        }
+    }
+
+    fn name(&self, seat: usize) -> String {
+        self.names.get(seat).cloned().unwrap_or_else(|| format!("Player {}", seat + 1))
+    }
+}
+
+// The table state an equity calculation was made for: hand seed, board size and who has folded
+type EquityKey = (u64, usize, Vec<bool>);
+
 struct MyGame {
     card_images: HashMap<String, Image>, // Multiple cards
+    card_back_image: Image, // Drawn for opponents' cards that are hidden
     players: Vec<FrontendPlayer>,
     chip_images: Vec<Image>, // stores pot images for different ranges
-    game_state: GameState,
-    backend_game: Game, // Backend game logic
+    backend_game: Game, // Backend game logic, only used when playing locally
+    view: PlayerView, // What is drawn. Made from backend_game when playing locally, sent by the server over the network
+    connection: Option<Connection>, // Some when playing at a game server
     elapsed_time: f32,
-    winner_index: Option<usize>,
     player_action: PlayerActions,
     pot: u32,
-    player_actions_done: Vec<bool>,
-    current_player_index: usize,
     slider_value: u32, 
+    slider_min: u32,
     slider_max: u32,
     slider_dragging: bool,
     show_slider: bool, 
     bet_button_clicked: bool,
-    last_raiser_index: Option<usize>,
     game_over: bool,
-    game_over_message: Option<String>
+    game_over_message: Option<String>,
+    action_error: Option<String>, // Why the last action was rejected, shown until the next valid action
+    status_message: Option<String>, // Network status, e.g. waiting for players or a lost connection
+    show_equity: bool, // Toggled with E, shows every player's chance to win
+    equity: Vec<Option<PlayerEquity>>, // Latest equity per player, None for folded players
+    equity_key: Option<EquityKey>, // The table state 'equity' belongs to, or is being calculated for
+    equity_receiver: Option<Receiver<(EquityKey, Vec<Option<PlayerEquity>>)>>, // Results from the background calculation
 }
 
 // Helper function to convert backend Card to image key
@@ -100,6 +231,31 @@ fn card_to_image_key(card: &Card) -> String {
     format!("{}_of_{}", value, suit)
 }
 
+// Where a player sits on the screen
+fn seat_position(seat: usize, seats: usize) -> Vec2 {
+    let spacing = 600.0 / (seats.max(2) - 1) as f32;
+    Vec2::new(100.0 + seat as f32 * spacing, 500.0)
+}
+
+// Playing locally every hand is shown, so the view is the current player's view with everyone's cards in it
+fn local_view(game: &Game) -> PlayerView {
+    let mut view = game.view_for(game.current_player);
+    view.players = game.players.clone();
+    view.showdown_hands = game.showdown_hands.clone();
+    view
+}
+
+// The button a player's action lights up
+fn action_to_player_action(action: Action) -> PlayerActions {
+    match action {
+        Action::Fold => PlayerActions::Fold,
+        Action::Check => PlayerActions::Check,
+        Action::Call => PlayerActions::Call,
+        Action::Bet(_) | Action::RaiseTo(_) => PlayerActions::Bet,
+        Action::AllIn => PlayerActions::AllIn,
+    }
+}
+
 // GUI function for loading all cards on the screen
 fn load_all_cards(context: &mut Context) -> HashMap<String, Image> {
     let suits = ["clubs", "spades", "diamonds", "hearts"];
@@ -122,8 +278,9 @@ fn load_all_cards(context: &mut Context) -> HashMap<String, Image> {
 }
 
 impl MyGame {
-    pub fn new(context: &mut Context) -> MyGame {
+    pub fn new(context: &mut Context, connection: Option<Connection>) -> MyGame {
         let card_images = load_all_cards(context);
+        let card_back_image = Image::from_path(context, "/card-backside.png").unwrap();
         let chip_images = vec![
             Image::from_path(context, "/pot1.png").unwrap(), // 0–99
             Image::from_path(context, "/pot2.png").unwrap(), // 100–499
@@ -131,11 +288,28 @@ impl MyGame {
             Image::from_path(context, "/pot4.png").unwrap(), // 750+
         ];
 
-        let mut backend_game = Game::new(2, 1000); // This needs to be changed when network is integrated
-
-        backend_game.deck.shuffle();
+        let mut backend_game = Game::new(2, 1000);
+
+        // Over the network the server deals, until its first view arrives the table is empty and nobody can act
+        let (view, status_message) = match &connection {
+            Some(connection) => {
+                let mut view = backend_game.view_for(0);
+                view.seat = connection.seat;
+                view.current_player = None;
+                view.players.clear();
+                view.legal_actions = LegalActions::default();
+                (view, Some("Waiting for players to join...".to_string()))
+            }
+            None => {
+                // Shuffles, deals and posts the blinds
+                if let Err(error) = backend_game.start_hand() {
+                    println!("Start hand error: {}", error);
+                }
+                (local_view(&backend_game), None)
+            }
+        };
 
-        // This is also gonna get changed when network is integrated
+        // Over the network the players are added when the server's view arrives
         let mut frontend_players = vec![
             FrontendPlayer {
                 name: "Joel".to_string(),
@@ -153,62 +327,195 @@ impl MyGame {
             },
         ];
 
-        // Deal cards to players
-        for player in 0..backend_game.players.len() {
-            if let Ok(cards) = backend_game.deck.draw(2) {
-                backend_game.players[player].hand.cards = cards;
-            }
+        if connection.is_some() {
+            frontend_players.clear();
         }
-
         for (i, player) in frontend_players.iter_mut().enumerate() {
             player.backend_player = backend_game.players[i].clone();
+            player.chips = backend_game.players[i].chips.chips;
         }
 
-        let slider_max = frontend_players[0].chips;
+        let slider_max = frontend_players.get(backend_game.current_player).map_or(0, |player| player.chips);
+        let pot = view.pot.total;
 
         MyGame {
             card_images,
+            card_back_image,
             players: frontend_players,
             chip_images,
-            game_state: GameState::Preflop,
             backend_game,
+            view,
+            connection,
             elapsed_time: 0.0,
-            winner_index: None,
             player_action: PlayerActions::None,
-            pot: 0,
-            player_actions_done: vec![false; 2],
-            current_player_index: 0,
+            pot,
             slider_value: 0, 
+            slider_min: 0,
             slider_max,
             slider_dragging: false,
             show_slider: false,
             bet_button_clicked: false,
-            last_raiser_index: None,
             game_over: false,
             game_over_message: None,
+            action_error: None,
+            status_message,
+            show_equity: false,
+            equity: Vec::new(),
+            equity_key: None,
+            equity_receiver: None,
         }
     }
 
     fn sync_pot_and_chips(&mut self) {
-        self.pot = self.backend_game.pot.total; // Sync pot with backend
+        // Playing locally the view is made from the backend, over the network it's the latest one from the server
+        if self.connection.is_none() {
+            self.view = local_view(&self.backend_game);
+        }
+        else if self.players.len() != self.view.players.len() {
+            self.players = (0..self.view.players.len())
+                .map(|i| FrontendPlayer {
+                    name: String::new(),
+                    chips: 0,
+                    backend_player: self.view.players[i].clone(),
+                    position: seat_position(i, self.view.players.len()),
+                    last_action: None,
+                })
+                .collect();
+        }
+
+        self.pot = self.view.pot.total; // Sync pot with backend
         for (i, player) in self.players.iter_mut().enumerate() {
-            player.chips = self.backend_game.players[i].chips.chips; // Sync players with chips
+            player.chips = self.view.players[i].chips.chips; // Sync players with chips
+            player.backend_player = self.view.players[i].clone();
+            if let Some(connection) = &self.connection {
+                player.name = connection.name(i);
+            }
+        }
+    }
+
+    // Handles everything the server has sent since the last frame
+    fn receive_network(&mut self) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+        let events: Vec<NetworkEvent> = connection.receiver.try_iter().collect();
+        for event in events {
+            match event {
+                NetworkEvent::Message(message) => self.handle_server_message(message),
+                NetworkEvent::Disconnected(reason) => {
+                    self.lose_connection();
+                    self.status_message = Some(format!("Disconnected from the server: {}. Reconnecting...", reason));
+                }
+                NetworkEvent::Reconnected(stream) => {
+                    if let Some(connection) = &mut self.connection {
+                        connection.stream = stream;
+                        connection.connected = true;
+                    }
+                    // The server sends the table as it is now right away
+                    self.status_message = None;
+                }
+                NetworkEvent::Closed(reason) => {
+                    self.lose_connection();
+                    self.status_message = Some(format!("Could not get back to the table: {}", reason));
+                }
+            }
+        }
+    }
+
+    // Nobody can act at a table we're no longer at
+    fn lose_connection(&mut self) {
+        if let Some(connection) = &mut self.connection {
+            connection.connected = false;
+        }
+        self.view.current_player = None;
+        self.view.legal_actions = LegalActions::default();
+        self.show_slider = false;
+        self.bet_button_clicked = false;
+    }
+
+    fn handle_server_message(&mut self, message: ServerMessage) {
+        let Some(connection) = &mut self.connection else {
+            return;
+        };
+        match message {
+            ServerMessage::View(view) => {
+                let new_street = view.street != self.view.street;
+                self.view = *view;
+                self.status_message = None;
+                self.sync_pot_and_chips();
+                if new_street {
+                    self.reset_actions();
+                }
+            }
+            ServerMessage::Seat { seat, name } => {
+                while connection.names.len() <= seat {
+                    connection.names.push(format!("Player {}", connection.names.len() + 1));
+                }
+                connection.names[seat] = name;
+                self.sync_pot_and_chips();
+            }
+            ServerMessage::Left { seat } => {
+                self.status_message = Some(format!("{} lost their connection, their seat is held for them", connection.name(seat)));
+            }
+            ServerMessage::Reconnected { seat } if seat != connection.seat => {
+                self.status_message = Some(format!("{} is back", connection.name(seat)));
+            }
+            ServerMessage::Acted { seat, action } => {
+                if let Some(player) = self.players.get_mut(seat) {
+                    player.last_action = Some(action_to_player_action(action));
+                }
+                if seat == connection.seat {
+                    self.action_error = None;
+                }
+            }
+            ServerMessage::Chat { seat, message } => println!("{}: {}", connection.name(seat), message),
+            ServerMessage::Error { message } => self.action_error = Some(message),
+            ServerMessage::Ping { id } => connection.send(&ClientMessage::Pong { id }),
+            ServerMessage::GameOver { winner } => {
+                self.game_over_message = Some(match winner {
+                    Some(seat) => format!("Game Over! {} wins!", connection.name(seat)),
+                    None => "Game Over! No chips left.".to_string(),
+                });
+                self.game_over = true;
+            }
+            // Our own entropy goes into the shuffle, so the server can't pick the deal even though it picks the seed.
+            // After a reconnect the same commitment comes again, the entropy for it may already be used
+            ServerMessage::Commit { commitment } => {
+                if connection.commitment != Some(commitment) {
+                    connection.commitment = Some(commitment);
+                    connection.entropy = rand::random::<[u8; 32]>().to_vec();
+                }
+                let entropy = connection.entropy.clone();
+                connection.send(&ClientMessage::Entropy { entropy });
+            }
+            // Checks our hole cards and the board against the seed the server committed to and our entropy
+            ServerMessage::Reveal { reveal, dealt_seats } => {
+                let Some(commitment) = connection.commitment.take() else {
+                    return;
+                };
+                let seen = seen_cards(&dealt_seats, connection.seat, self.view.hole_cards(), &self.view.board.cards);
+                if let Err(error) = verify(&commitment, &reveal, &seen) {
+                    self.status_message = Some(format!("The deal couldn't be verified: {}", error));
+                }
+                else if !reveal.has_entropy(&connection.entropy) {
+                    self.status_message = Some("The deal didn't use our entropy, the server could have picked it".to_string());
+                }
+            }
+            // The View has the same cards
+            ServerMessage::Deal { .. } | ServerMessage::Showdown { .. } => {}
+            ServerMessage::Welcome { .. } | ServerMessage::Reconnected { .. } | ServerMessage::Pong { .. } => {}
         }
     }
     
     // Reset the game when a player has won or pressed R (single player verison)
     fn reset_game(&mut self) {
         self.backend_game = Game::new(2, 1000);
-        self.backend_game.deck.shuffle();
-        for player in 0..self.backend_game.players.len() {
-            if let Ok(cards) = self.backend_game.deck.draw(2) {
-                self.backend_game.players[player].hand.cards = cards;
-                self.last_raiser_index = None;
-                self.game_over = false;
-                self.game_over_message = None;
-                self.sync_pot_and_chips();
-            }
+        if let Err(error) = self.backend_game.start_hand() {
+            println!("Start hand error: {}", error);
         }
+        self.game_over = false;
+        self.game_over_message = None;
+        self.sync_pot_and_chips();
     
         // Sync backend state to frontend state
         for (i, player) in self.players.iter_mut().enumerate() {
@@ -216,191 +523,220 @@ impl MyGame {
         }
 
         // Reset game variables
-        self.game_state = GameState::Preflop;
-        self.elapsed_time = 0.0;
-        self.winner_index = None;
-        self.pot = 0;
+        self.reset_actions();
     }
 
     // Reset actions to be able to do all actions in the next game-phase
     fn reset_actions(&mut self) {
-        self.player_actions_done = vec![false; self.players.len()];
         self.elapsed_time = 0.0;
         self.player_action = PlayerActions::None;
 
-        // Reset who raised
-        self.last_raiser_index = None;
+        self.slider_value = 0;
+        self.update_slider_bounds();
+    }
+
+    // The slider goes from the smallest to the largest bet or raise the current player is allowed to make
+    fn update_slider_bounds(&mut self) {
+        let legal = self.view.legal_actions;
+        let player_bet = self.view.pot.player_bets.get(self.view.seat).copied().unwrap_or(0);
+        if legal.can_bet || legal.can_raise {
+            self.slider_min = legal.min_raise_to - player_bet;
+            self.slider_max = legal.max_raise_to - player_bet;
+        }
+        else {
+            self.slider_min = 0;
+            self.slider_max = 0;
+        }
+        self.slider_value = self.slider_value.clamp(self.slider_min, self.slider_max);
+    }
 
-        // Start with next non-folded player
-        self.current_player_index = self.players
-        .iter()
-        .position(|predicate| !predicate.backend_player.is_folded)
-        .unwrap_or(0);
+    // Whether the current player is allowed to take an action, used to grey out buttons
+    fn action_allowed(&self, action: PlayerActions) -> bool {
+        let legal = self.view.legal_actions;
+        match action {
+            PlayerActions::Bet => legal.can_bet || legal.can_raise,
+            PlayerActions::Check => legal.can_check,
+            PlayerActions::Call => legal.can_call,
+            PlayerActions::Fold => legal.can_fold,
+            PlayerActions::AllIn => legal.can_all_in,
+            PlayerActions::None => false,
+        }
+    }
 
-        self.slider_max = self.players[self.current_player_index].chips;
-        self.slider_value = 0;
+    // The table state equity depends on, a new calculation is needed when it changes
+    fn current_equity_key(&self) -> EquityKey {
+        let folded = self.backend_game.players.iter().map(|player| player.is_folded).collect();
+        (self.backend_game.hand_seed, self.backend_game.board.cards.len(), folded)
     }
 
-    // Track players who have folded to see what player's turn is next
-    fn find_next_active_player(&self, from: usize) -> usize{
-        let mut index = from;
-        while self.players[index].chips == 0 || self.backend_game.players[index].is_folded {
-            index = (index + 1) % self.players.len();
-        } 
-        index
+    // Starts calculating every player's equity on a background thread so the frames don't stall
+    fn request_equity(&mut self) {
+        let key = self.current_equity_key();
+        self.equity_key = Some(key.clone());
+        self.equity.clear();
+
+        let in_hand: Vec<(usize, Hand)> = self.backend_game.players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| !player.is_folded)
+            .map(|(i, player)| (i, player.hand.clone()))
+            .collect();
+        if in_hand.len() < 2 {
+            self.equity_receiver = None;
+            return;
+        }
+        let board = self.backend_game.board.clone();
+        let players = self.backend_game.players.len();
+
+        let (sender, receiver) = mpsc::channel();
+        self.equity_receiver = Some(receiver);
+        thread::spawn(move || {
+            let hands: Vec<Hand> = in_hand.iter().map(|(_, hand)| hand.clone()).collect();
+            let mut equity = vec![None; players];
+            if let Ok(result) = calculate_equity(&hands, &board, CardSet::new(), EquitySettings::default()) {
+                for ((i, _), player_equity) in in_hand.iter().zip(result.players) {
+                    equity[*i] = Some(player_equity);
+                }
+            }
+            // The receiver is gone if the table changed while calculating, the result isn't needed then
+            let _ = sender.send((key, equity));
+        });
+    }
+
+    // Picks up a finished equity calculation, results for an older table state are thrown away
+    fn receive_equity(&mut self) {
+        let Some(receiver) = &self.equity_receiver else {
+            return;
+        };
+        if let Ok((key, equity)) = receiver.try_recv() {
+            if self.equity_key.as_ref() == Some(&key) {
+                self.equity = equity;
+            }
+            self.equity_receiver = None;
+        }
     }
 
-    fn determine_winner(&self) -> usize {
-        self.backend_game.best_hand()
+    // Where the knob is on the slider, from 0.0 to 1.0
+    fn slider_percent(&self) -> f32 {
+        if self.slider_max == self.slider_min {
+            return 0.0;
+        }
+        (self.slider_value - self.slider_min) as f32 / (self.slider_max - self.slider_min) as f32
+    }
+
+    // The slider value for a mouse x position
+    fn slider_value_at(&self, x: f32) -> u32 {
+        let slider_x = 300.0;
+        let slider_width = 300.0;
+
+        let clamped_x = x.clamp(slider_x, slider_x + slider_width);
+        let percent = (clamped_x - slider_x) / slider_width;
+        self.slider_min + ((percent * (self.slider_max - self.slider_min) as f32).round()) as u32
     }
 }
 
 
 impl EventHandler for MyGame {
     fn update(&mut self, context: &mut Context) -> GameResult {
-        let delta = ggez::timer::delta(context).as_secs_f32();
+        let delta = context.time.delta().as_secs_f32();
         self.elapsed_time += delta;
 
+        self.receive_network();
+
+        // Live equity overlay, recalculated when a street is dealt or a player folds. Only playing locally, where every hand is known
+        if self.show_equity && self.connection.is_none() && !self.backend_game.hand_over() {
+            if self.equity_key != Some(self.current_equity_key()) {
+                self.request_equity();
+            }
+            self.receive_equity();
+        }
+
         // Slider for betting
         if self.slider_dragging && self.slider_max > 0 {
-            let mouse_x = ggez::input::mouse::position(context).x;
-            let relative = (mouse_x - 600.0).clamp(0.0, 300.0);
-            self.slider_value = ((relative / 300.0) * self.slider_max as f32) as u32;
+            let mouse_x = context.mouse.position().x;
+            self.slider_value = self.slider_value_at(mouse_x);
         }
 
-        // Handle player actions using backend logic
-        if !self.player_actions_done[self.current_player_index] {
-            match self.player_action {
+        // Handle player actions using backend logic, the backend (or the server) keeps track of whose turn it is
+        if self.player_action != PlayerActions::None && !self.view.hand_over() {
+            let current_player_index = self.view.seat;
+            let action = match self.player_action {
                 PlayerActions::Bet => {
+                    // The slider is the amount of chips put in, so with a bet to face it's a raise on top of what the player already has in
                     let bet_amount = self.slider_value;
-                    if self.backend_game.bet(self.current_player_index, bet_amount).is_ok() {
-                        self.last_raiser_index = Some(self.current_player_index);
-                    }
-                    else {
-                        println!("Error with betting");
-                    }
-                }
-            PlayerActions::Check => {
-                    if let Err(error) = self.backend_game.check(self.current_player_index) {
-                        println!("Check error: {}", error);
+                    if self.view.pot.current_bet == 0 {
+                        Action::Bet(bet_amount)
+                    } else {
+                        Action::RaiseTo(self.view.pot.player_bets[current_player_index] + bet_amount)
                     }
                 }
-            PlayerActions::Call => {
-                if let Err(error) = self.backend_game.call(self.current_player_index) {
-                    println!("Call error: {}", error);
-                }
-            }
-            PlayerActions::Fold => {
-                self.backend_game.fold(self.current_player_index);
+                PlayerActions::Check => Action::Check,
+                PlayerActions::Call => Action::Call,
+                PlayerActions::Fold => Action::Fold,
+                PlayerActions::AllIn => Action::AllIn,
+                PlayerActions::None => return Ok(()),
+            };
+
+            // Over the network the server checks the action and sends the new view back
+            if let Some(connection) = &mut self.connection {
+                connection.send(&ClientMessage::Action(action));
+                self.player_action = PlayerActions::None;
+                return Ok(());
             }
-            PlayerActions::None => return Ok(()),
-        }
 
-        // When an action is done:
-        self.sync_pot_and_chips();
-        self.player_actions_done[self.current_player_index] = true;
-        self.player_action = PlayerActions::None;
+            match self.backend_game.apply_action(current_player_index, action) {
+                Ok(()) => {
+                    self.players[current_player_index].last_action = Some(self.player_action);
+                    self.action_error = None;
+                }
+                Err(error) => self.action_error = Some(error.to_string()),
+            }
 
-        // Advance to next player's turn
-        let mut next_index = (self.current_player_index + 1) % self.players.len();
-        while self.backend_game.players[next_index].is_folded {
-            next_index = (next_index + 1) % self.players.len();
+            // When an action is done:
+            self.sync_pot_and_chips();
+            self.player_action = PlayerActions::None;
+            self.update_slider_bounds();
         }
-        self.current_player_index = next_index;
 
-        self.slider_max = self.players[self.current_player_index].chips;
-        self.slider_value = self.slider_value.min(self.slider_max);
-    }
+        // The server deals and starts the next hands
+        if self.connection.is_some() {
+            return Ok(());
+        }
 
-        // Check if round is ready to advance
-        let all_acted = self.player_actions_done
+        // Advance to the next street when the betting round is over. The backend deals the board and awards the pots
+        if !self.backend_game.hand_over() {
+            if self.backend_game.betting_round_over() {
+                if let Err(error) = self.backend_game.advance() {
+                    println!("Advance error: {}", error);
+                }
+                self.sync_pot_and_chips();
+                self.reset_actions();
+            }
+        }
+        // Check how many are alive and if someone has won the game
+        else if self.elapsed_time > 3.0 && !self.game_over {
+            let alive_players: Vec<_> = self.players
             .iter()
-            .enumerate()
-            .all(|(i, acted)| self.backend_game.players[i].is_folded || *acted);
-
-        let everyone_matched = self.backend_game.non_folded_players_match_bet();
-
-        // Advance to the next phase. Here's also where all the gamephases are handled
-        if all_acted && everyone_matched {
-            self.elapsed_time += delta;
-            
-            match self.game_state {
-                // First phase
-                GameState::Preflop => {
-                        // Deal flop (3 cards)
-                        if let Ok(flop_cards) = self.backend_game.deck.draw(3) {
-                            self.backend_game.board.extend(flop_cards);
-                        }
-                        self.reset_actions();
-                        self.backend_game.reset_round();
-                        self.game_state = GameState::Flop;
-                    
-                },
-                // Second phase
-                GameState::Flop => {
-                        // Deal turn (1 card)
-                        if let Ok(turn_card) = self.backend_game.deck.draw(1) {
-                            self.backend_game.board.extend(turn_card);
-                        }
-                        self.reset_actions();
-                        self.backend_game.reset_round();
-                        self.players[self.current_player_index].last_action = Some(self.player_action.clone());
-                        self.game_state = GameState::Turn;
-                    
-                },
-                // Third phase
-                GameState::Turn => {
-                        // Deal river (1 card)
-                        if let Ok(river_card) = self.backend_game.deck.draw(1) {
-                            self.backend_game.board.extend(river_card);
-                        }
-                        self.reset_actions();
-                        self.backend_game.reset_round();
-                        self.players[self.current_player_index].last_action = Some(self.player_action.clone());
-                        self.game_state = GameState::River;
-                    
-                },
-                // Fourth phase
-                GameState::River => {
-                        self.game_state = GameState::Showdown;                    
-                },
-                // Fifth and last phase
-                GameState::Showdown => {
-                    // Set round winner once on first showdown frame
-                    if self.winner_index.is_none() {
-                        self.winner_index = Some(self.determine_winner());
-                        self.backend_game.award_pot_to_winner();
-                        self.elapsed_time = 0.0; // Reset timer when entering showdown
-                    }
-                    // Check how many are alive and if someone has won the game
-                    if self.elapsed_time > 3.0 {
-                        let alive_players: Vec<_> = self.players
-                        .iter()
-                        .filter(|predicate| predicate.chips > 0)
-                        .collect();
-                        if alive_players.len() <= 1 {
-                            if let Some(winner) = alive_players.first() {
-                                self.game_over_message = Some(format!("Game Over! {} wins!", winner.name));
-                            } else {
-                                self.game_over_message = Some("Game Over! No chips left.".to_string());
-                            }
-                            self.game_over = true;
-                        }
-                        // Otherwise restart round and keep going
-                        else {
-                            self.backend_game.reset_round();
-                            self.sync_pot_and_chips();
-                            self.reset_actions();
-                            self.game_state = GameState::Preflop;
-                            self.winner_index = None;
-                            self.current_player_index = self.find_next_active_player(0);
-                            self.slider_max = self.players[self.current_player_index].chips;
-                            self.slider_value = 0;
-                        }
-                    }
+            .filter(|predicate| predicate.chips > 0)
+            .collect();
+            if alive_players.len() <= 1 {
+                if let Some(winner) = alive_players.first() {
+                    self.game_over_message = Some(format!("Game Over! {} wins!", winner.name));
+                } else {
+                    self.game_over_message = Some("Game Over! No chips left.".to_string());
                 }
+                self.game_over = true;
+            }
+            // Otherwise restart round and keep going
+            else {
+                // Moves the button, deals new cards and posts the blinds for the next hand
+                if let Err(error) = self.backend_game.start_hand() {
+                    println!("Start hand error: {}", error);
                 }
+                self.sync_pot_and_chips();
+                self.reset_actions();
             }
+        }
         Ok(())
     }
 
@@ -490,42 +826,39 @@ impl EventHandler for MyGame {
         )?;
         canvas.draw(&right_circle, graphics::DrawParam::default());
 
+        // Highlight when a player wins
+        let winner_indices = &self.view.showdown_winners;
+
+        // The cards that make up the winning hands, they are highlighted on the board and in the hole cards
+        let winning_cards: Vec<Card> = winner_indices
+            .iter()
+            .filter_map(|&i| self.view.showdown_hands.get(i).cloned().flatten())
+            .flat_map(|strength| strength.cards)
+            .collect();
+
         // Draw community cards
-        for (i, card) in self.backend_game.board.iter().enumerate() {
+        for (i, card) in self.view.board.cards.iter().enumerate() {
             let card_key = card_to_image_key(card);
             if let Some(card_image) = self.card_images.get(&card_key) {
-                canvas.draw(card_image, graphics::DrawParam::default()
-                .dest(Vec2::new(200.0 + i as f32 * 110.0, 200.0))
-                .scale(Vec2::new(0.14, 0.14)),
-                );
+                let mut parameter = graphics::DrawParam::default()
+                    .dest(Vec2::new(200.0 + i as f32 * 110.0, 200.0))
+                    .scale(Vec2::new(0.14, 0.14));
+                if winning_cards.contains(card) {
+                    parameter = parameter.color(Color::from_rgb(255, 255, 150));
+                }
+                canvas.draw(card_image, parameter);
             }
         }
         
-        // Highlight when a player wins
-        let winner_index = if self.game_state == GameState::Showdown {
-            Some(self.determine_winner())
-        } else {
-            None
-        };
-        
         for (i, player) in self.players.iter().enumerate() {
             let mut display_text = player.name.clone();
         
-            if self.game_state == GameState::Showdown {
-                if i < self.backend_game.players.len() {
-                    let (rank, hand_type) = self.backend_game.players[i].hand.evaluate(
-                        &self.backend_game.board,
-                        &self.backend_game.t5,
-                        &self.backend_game.t7
-                    );
-                    display_text = format!("{}: {}", player.name, hand_type);
-                }
+            // Only filled in when the hand has ended
+            if let Some(Some(strength)) = self.view.showdown_hands.get(i) {
+                display_text = format!("{}: {}", player.name, strength.description());
             }
         
-            let is_winner = match winner_index {
-                Some(winner) => i == winner,
-                None => false,
-            };
+            let is_winner = winner_indices.contains(&i);
         
             if is_winner {
                 display_text = format!("{} wins!", display_text);
@@ -533,16 +866,40 @@ impl EventHandler for MyGame {
         
             let name_text = graphics::Text::new(display_text);
             canvas.draw(&name_text, DrawParam::default().dest(player.position));
+
+            // Equity overlay above the name, while the hand is being played
+            if self.show_equity && self.connection.is_none() && !self.backend_game.hand_over() && !self.backend_game.players[i].is_folded {
+                let equity_text = match self.equity.get(i) {
+                    Some(Some(equity)) if equity.tie > 0.0 => format!("Win: {:.1}% (tie {:.1}%)", equity.win, equity.tie),
+                    Some(Some(equity)) => format!("Win: {:.1}%", equity.win),
+                    _ => "Win: ...".to_string(),
+                };
+                canvas.draw(
+                    &Text::new(equity_text),
+                    DrawParam::default().dest(player.position - Vec2::new(0.0, 25.0)).color(Color::YELLOW)
+                );
+            }
         
-            // Draw players hand (singleplayer version)
-            for (j, card) in self.backend_game.players[i].hand.cards.iter().enumerate() {
+            // Opponents' cards that aren't shown are drawn face down, as long as they are in the hand
+            let view_player = &self.view.players[i];
+            if view_player.hand.cards.is_empty() && !view_player.is_folded && !self.view.hand_over() {
+                for j in 0..2 {
+                    let parameter = DrawParam::default()
+                        .dest(player.position + Vec2::new(j as f32 * 40.0, 30.0))
+                        .scale(Vec2::new(0.204, 0.204));
+                    canvas.draw(&self.card_back_image, parameter);
+                }
+            }
+
+            // Draw players hand
+            for (j, card) in view_player.hand.cards.iter().enumerate() {
                 let card_key = card_to_image_key(card);
                 if let Some(card_image) = self.card_images.get(&card_key) {
                     let mut parameter = DrawParam::default()
                         .dest(player.position + Vec2::new(j as f32 * 40.0, 30.0))
                         .scale(Vec2::new(0.28, 0.28));
-                    // Make the cards yellow to represent the winner even more
-                    if is_winner {
+                    // Make the cards that are part of the winning hand yellow to represent the winner even more
+                    if is_winner && winning_cards.contains(card) {
                         parameter = parameter.color(Color::from_rgb(255, 255, 150));
                     }
         
@@ -551,25 +908,6 @@ impl EventHandler for MyGame {
             }
         }
 
-        /*
-        // Draw players hand (multiplayer version)
-        for (i, player) in self.players.iter().enumerate() {
-            let is_you = i == self.local_player_index; // Defined later in backend/network
-            let is_showdown = self.game_state = GameState::Showdown;
-
-            for (j, card) in player.backend_player.hand.cards.iter().enumerate() {
-                let card_image = if is_you || is_showdown {
-                    &self.card_images[card.index()] // the card.index() method should give each card a unique ID for image lookup
-                }
-                else {
-                    &self.card_back_image // an image of the back of a card
-                };
-                let position = player.position + Vec2::new((j * 40) as f32, 0.0);
-                canvas.draw(card_image, DrawParam::default().dest(position));
-            }
-        }
-        */
-
         // Set pot ranges for the different pot-pngs
         let chip_index = match self.pot {
             0..= 99 => 0,
@@ -590,18 +928,30 @@ impl EventHandler for MyGame {
             .dest(Vec2::new(100.0, 370.0))
     );
 
-        // Draw action buttons
-        let button_labbels = ["Bet", "Check", "Call", "Fold"];
+        // Draw action buttons, greyed out when the action isn't allowed
+        let button_labbels = [
+            (PlayerActions::Bet, "Bet"),
+            (PlayerActions::Check, "Check"),
+            (PlayerActions::Call, "Call"),
+            (PlayerActions::Fold, "Fold"),
+            (PlayerActions::AllIn, "All In"),
+        ];
         
-        for (i, label) in button_labbels.iter().enumerate() {
+        for (i, (action, label)) in button_labbels.iter().enumerate() {
             let x = 50.0 + i as f32 * 130.0;
             let y = 100.0;
             let rect = Rect::new(x, y, 120.0, 50.0);
+            let allowed = self.action_allowed(*action);
+            let (button_color, text_color) = if allowed {
+                (Color::from_rgb(50, 50, 50), Color::WHITE)
+            } else {
+                (Color::from_rgb(90, 90, 90), Color::from_rgb(150, 150, 150))
+            };
             let button = graphics::Mesh::new_rectangle(
                 context,
                 DrawMode::fill(),
                 rect,
-                Color::from_rgb(50, 50, 50)
+                button_color
             )?;
 
         canvas.draw(&button, DrawParam::default());
@@ -609,7 +959,8 @@ impl EventHandler for MyGame {
         let text = graphics::Text::new((*label).to_string());
         canvas.draw(&text, DrawParam::default()
         .dest(Vec2::new(x + 30.0, y + 15.0))
-        .scale(Vec2::new(1.5, 1.5)),
+        .scale(Vec2::new(1.5, 1.5))
+        .color(text_color),
         );
         }
 
@@ -619,11 +970,8 @@ impl EventHandler for MyGame {
         let slider_width = 300.0;
         let knob_radius = 10.0;
 
-        // Make boundary depending on how many chips a player has
-        self.slider_max = self.players[self.current_player_index].chips;
-        if self.slider_value > self.slider_max {
-            self.slider_value = self.slider_max;
-            }
+        // Make boundary depending on what the player is allowed to bet
+        self.update_slider_bounds();
 
         // Slider shows when bet is clicked
         if self.show_slider {
@@ -638,7 +986,7 @@ impl EventHandler for MyGame {
         canvas.draw(&track, DrawParam::default());
 
         // Movable knob for the slider
-        let knob_position = slider_x + (self.slider_value as f32 / self.slider_max as f32) * slider_width;
+        let knob_position = slider_x + self.slider_percent() * slider_width;
         let knob = graphics::Mesh::new_circle(
             context, 
             DrawMode::fill(), 
@@ -667,6 +1015,18 @@ impl EventHandler for MyGame {
             }
         }
 
+        // Draw why the last action wasn't allowed
+        if let Some(ref error) = self.action_error {
+            let text = Text::new(error.as_str());
+            canvas.draw(&text, DrawParam::default().dest(Vec2::new(50.0, 160.0)).color(Color::RED));
+        }
+
+        // Draw the network status
+        if let Some(ref message) = self.status_message {
+            let text = Text::new(message.as_str());
+            canvas.draw(&text, DrawParam::default().dest(Vec2::new(50.0, 185.0)).color(Color::WHITE));
+        }
+
         // Draw game over text
         if let Some(ref message) = self.game_over_message {
             let fragment = TextFragment::new(message.as_str()).scale(36.0);
@@ -694,10 +1054,15 @@ impl EventHandler for MyGame {
                     (PlayerActions::Check, Rect::new(180.0, 100.0, 120.0, 50.0)),
                     (PlayerActions::Call, Rect::new(310.0, 100.0, 120.0, 50.0)),
                     (PlayerActions::Fold, Rect::new(440.0, 100.0, 120.0, 50.0)),
+                    (PlayerActions::AllIn, Rect::new(570.0, 100.0, 120.0, 50.0)),
                 ];
 
                 for (action, rect) in buttons.iter() {
-                    if rect.contains([x, y]).into() {
+                    if rect.contains([x, y]) {
+                        // Greyed out buttons can't be clicked
+                        if !self.action_allowed(*action) {
+                            break;
+                        }
                         println!("Player chose to {:?}", action);
 
                         if *action == PlayerActions::Bet {
@@ -726,7 +1091,7 @@ impl EventHandler for MyGame {
                     let slider_width = 300.0;
                     let knob_radius = 10.0;
         
-                    let knob_x = slider_x + (self.slider_value as f32 / self.slider_max as f32) * slider_width;
+                    let knob_x = slider_x + self.slider_percent() * slider_width;
                     let knob_y = slider_y + 2.0;
 
                     let knob_hitbox = Rect::new(
@@ -746,9 +1111,7 @@ impl EventHandler for MyGame {
                     if knob_hitbox.contains::<[f32; 2]>([x, y]) || track_hitbox.contains::<[f32; 2]>([x, y]) {
                         self.slider_dragging = true;
                         // Immediately update slider value based on click position
-                        let clamped_x = x.clamp(slider_x, slider_x + slider_width);
-                        let percent = (clamped_x - slider_x) / slider_width;
-                        self.slider_value = ((percent * self.slider_max as f32).round()) as u32;
+                        self.slider_value = self.slider_value_at(x);
                     }
                 }
             }
@@ -760,8 +1123,16 @@ impl EventHandler for MyGame {
             input: KeyInput,
             _repeated: bool,
         ) -> GameResult {
-        if let Some(KeyCode::R) = input.keycode {
-            self.reset_game();
+        match input.keycode {
+            // Only playing locally, the server decides when a new game starts
+            Some(KeyCode::R) if self.connection.is_none() => self.reset_game(),
+            // Toggle the live equity overlay
+            Some(KeyCode::E) => {
+                self.show_equity = !self.show_equity;
+                self.equity_key = None;
+                self.equity_receiver = None;
+            }
+            _ => {}
         }
         Ok(())
     }
@@ -775,12 +1146,7 @@ impl EventHandler for MyGame {
             _dy: f32,
         ) -> GameResult {
         if self.slider_dragging {
-            let slider_x = 300.0;
-            let slider_width = 300.0;
-
-            let clamped_x = x.clamp(slider_x, slider_x + slider_width);
-            let percent = (clamped_x - slider_x) / slider_width;
-            self.slider_value = ((percent * self.slider_max as f32).round()) as u32;
+            self.slider_value = self.slider_value_at(x);
         }
         Ok(())
     }
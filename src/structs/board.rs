@@ -0,0 +1,106 @@
+use super::card::{parse_cards, Card};
+use super::error::ParseCardError;
+use super::hand::Hand;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// The community cards, up to five (flop, turn and river)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Board {
+    pub cards: Vec<Card>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Board {
+    pub fn new() -> Self {
+        Board { cards: Vec::new() }
+    }
+}
+
+// The cards next to each other, e.g. "QsJs9d". An empty board is an empty string
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for card in &self.cards {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards = parse_cards(s)?;
+        if cards.len() > 5 {
+            return Err(ParseCardError::WrongCardCount { expected: "0-5".to_string(), found: cards.len() });
+        }
+        Ok(Board { cards })
+    }
+}
+
+/// Parses a hand followed by a board, e.g. "AhKh QsJs9d".
+///
+/// # Returns
+/// - A Result<(Hand, Board), ParseCardError> where:
+///     - (Hand, Board): the first two cards as the hand and the rest (0-5 cards) as the board.
+///     - ParseCardError: if a card can't be parsed, a card is there twice or there are too few or too many cards.
+///
+/// # Example
+/// '''
+/// let (hand, board) = parse_hand_and_board("AhKh QsJs9d")?;
+/// println!("{} on {}", hand, board); // "AhKh on QsJs9d"
+/// '''
+pub fn parse_hand_and_board(s: &str) -> Result<(Hand, Board), ParseCardError> {
+    let mut cards = parse_cards(s)?;
+    if !(2..=7).contains(&cards.len()) {
+        return Err(ParseCardError::WrongCardCount { expected: "2-7".to_string(), found: cards.len() });
+    }
+    let board = cards.split_off(2);
+    Ok((Hand { cards }, Board { cards: board }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_round_trips() {
+        for text in ["", "Qs", "QsJs9d", "QsJs9dTc", "QsJs9dTc2h"] {
+            let board: Board = text.parse().unwrap();
+            assert_eq!(board.cards.len(), text.len() / 2);
+            assert_eq!(board.to_string(), text);
+        }
+        assert_eq!("qs js 9d".parse::<Board>().unwrap().to_string(), "QsJs9d");
+    }
+
+    #[test]
+    fn board_errors() {
+        assert_eq!("QsJs9dTc2h3h".parse::<Board>(), Err(ParseCardError::WrongCardCount { expected: "0-5".to_string(), found: 6 }));
+        assert_eq!("QsJsQs".parse::<Board>(), Err(ParseCardError::DuplicateCard("Qs".to_string())));
+        assert_eq!("QsJs9".parse::<Board>(), Err(ParseCardError::MissingSuit('9')));
+        assert_eq!("QsJz".parse::<Board>(), Err(ParseCardError::InvalidSuit('z')));
+        assert_eq!("QsBs".parse::<Board>(), Err(ParseCardError::InvalidRank('B')));
+    }
+
+    #[test]
+    fn hand_and_board() {
+        let (hand, board) = parse_hand_and_board("AhKh QsJs9d").unwrap();
+        assert_eq!(hand.to_string(), "AhKh");
+        assert_eq!(board.to_string(), "QsJs9d");
+
+        let (hand, board) = parse_hand_and_board("AhKh").unwrap();
+        assert_eq!(hand.to_string(), "AhKh");
+        assert!(board.cards.is_empty());
+
+        assert_eq!(parse_hand_and_board("Ah"), Err(ParseCardError::WrongCardCount { expected: "2-7".to_string(), found: 1 }));
+        assert_eq!(parse_hand_and_board("AhKh QsJs9dTc2h3h"), Err(ParseCardError::WrongCardCount { expected: "2-7".to_string(), found: 8 }));
+        assert_eq!(parse_hand_and_board("AhKh Ah"), Err(ParseCardError::DuplicateCard("Ah".to_string())));
+    }
+}
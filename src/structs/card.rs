@@ -1,11 +1,19 @@
 use super::enums::{Rank, Suit};
+use super::error::ParseCardError;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+// Cards are ordered by rank first (ace high) and suit after
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Card {
     pub suit: Suit,
     pub rank: Rank,
 }
+
 impl Card {
+    // Index from 0 to 51, four per rank starting from the twos (2c, 2d, 2h, 2s, 3c, ...)
     pub fn as_index(self) -> usize {
         let suit_offset = match self.suit {
             Suit::Clubs => 0,
@@ -13,23 +21,125 @@ impl Card {
             Suit::Hearts => 2,
             Suit::Spades => 3,
         };
-    
-        let rank_offset = match self.rank {
-            Rank::Two => 0,
-            Rank::Three => 4,
-            Rank::Four => 8,
-            Rank::Five => 12,
-            Rank::Six => 16,
-            Rank::Seven => 20,
-            Rank::Eight => 24,
-            Rank::Nine => 28,
-            Rank::Ten => 32,
-            Rank::Jack => 36,
-            Rank::Queen => 40,
-            Rank::King => 44,
-            Rank::Ace => 48,
+        self.rank.index() * 4 + suit_offset
+    }
+
+    // The card for an index from as_index(), None if the index is 52 or more
+    pub fn from_index(index: usize) -> Option<Card> {
+        let suit = match index % 4 {
+            0 => Suit::Clubs,
+            1 => Suit::Diamonds,
+            2 => Suit::Hearts,
+            _ => Suit::Spades,
         };
-    
-        suit_offset + rank_offset
+        Some(Card { suit, rank: Rank::from_index(index / 4)? })
+    }
+}
+
+impl Ord for Card {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.rank, self.suit).cmp(&(other.rank, other.suit))
+    }
+}
+
+impl PartialOrd for Card {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Two character notation, rank then suit, e.g. "Ah", "Td" or "2c"
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.rank.to_char(), self.suit.to_char())
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards = parse_cards(s)?;
+        match cards.as_slice() {
+            [card] => Ok(*card),
+            _ => Err(ParseCardError::WrongCardCount { expected: "1".to_string(), found: cards.len() }),
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Parses cards written in two character notation, e.g. "AhKh QsJs9d".
+///
+/// # Behavior
+/// - Whitespace between cards is ignored, "AhKh" and "Ah Kh" give the same cards.
+/// - Ranks are A, K, Q, J, T and 2-9, suits are s, c, h and d. Either case is accepted.
+/// - A card that is there more than once gives a DuplicateCard error.
+///
+/// # Example
+/// '''
+/// let cards = parse_cards("AhKh QsJs9d")?;
+/// assert_eq!(cards.len(), 5);
+/// '''
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    let mut cards: Vec<Card> = Vec::new();
+    let mut chars = s.chars().filter(|c| !c.is_whitespace());
+    while let Some(rank_char) = chars.next() {
+        let rank = Rank::from_char(rank_char).ok_or(ParseCardError::InvalidRank(rank_char))?;
+        let suit_char = chars.next().ok_or(ParseCardError::MissingSuit(rank_char))?;
+        let suit = Suit::from_char(suit_char).ok_or(ParseCardError::InvalidSuit(suit_char))?;
+
+        let card = Card { suit, rank };
+        if cards.contains(&card) {
+            return Err(ParseCardError::DuplicateCard(card.to_string()));
+        }
+        cards.push(card);
+    }
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_card() -> Vec<Card> {
+        (0..52).filter_map(Card::from_index).collect()
+    }
+
+    #[test]
+    fn every_card_round_trips() {
+        let cards = every_card();
+        assert_eq!(cards.len(), 52);
+        for (index, card) in cards.iter().enumerate() {
+            assert_eq!(card.as_index(), index);
+            let text = card.to_string();
+            assert_eq!(text.len(), 2);
+            assert_eq!(text.parse::<Card>(), Ok(*card));
+            assert_eq!(text.to_lowercase().parse::<Card>(), Ok(*card));
+        }
+        assert_eq!(Card::from_index(52), None);
+
+        let text: String = cards.iter().map(|card| card.to_string()).collect::<Vec<_>>().join(" ");
+        assert_eq!(parse_cards(&text), Ok(cards));
+    }
+
+    #[test]
+    fn notation() {
+        assert_eq!("Ah".parse(), Ok(Card { suit: Suit::Hearts, rank: Rank::Ace }));
+        assert_eq!("Td".parse(), Ok(Card { suit: Suit::Diamonds, rank: Rank::Ten }));
+        assert_eq!("2c".parse(), Ok(Card { suit: Suit::Clubs, rank: Rank::Two }));
+        assert_eq!(" 9s ".parse(), Ok(Card { suit: Suit::Spades, rank: Rank::Nine }));
+        assert_eq!(parse_cards("AhKh QsJs9d"), parse_cards("Ah Kh Qs Js 9d"));
+        assert_eq!(parse_cards(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("Xh".parse::<Card>(), Err(ParseCardError::InvalidRank('X')));
+        assert_eq!("1h".parse::<Card>(), Err(ParseCardError::InvalidRank('1')));
+        assert_eq!("Ax".parse::<Card>(), Err(ParseCardError::InvalidSuit('x')));
+        assert_eq!("A".parse::<Card>(), Err(ParseCardError::MissingSuit('A')));
+        assert_eq!(parse_cards("AhKhA"), Err(ParseCardError::MissingSuit('A')));
+        assert_eq!(parse_cards("AhKhah"), Err(ParseCardError::DuplicateCard("Ah".to_string())));
+        assert_eq!("AhKh".parse::<Card>(), Err(ParseCardError::WrongCardCount { expected: "1".to_string(), found: 2 }));
+        assert_eq!("".parse::<Card>(), Err(ParseCardError::WrongCardCount { expected: "1".to_string(), found: 0 }));
+    }
+}
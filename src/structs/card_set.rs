@@ -0,0 +1,229 @@
+use super::card::Card;
+use std::fmt;
+
+const FULL_DECK: u64 = (1 << 52) - 1;
+
+/// A set of cards stored as a u64 bitmask, bit Card::as_index() is set when the card is in the set.
+///
+/// Copying, set operations and lookups are single integer operations, which makes it a better fit than
+/// Vec<Card> for simulations and for keeping track of which cards are dead.
+///
+/// # Example
+/// '''
+/// let mut known = CardSet::from(&hand.cards[..]);
+/// known.extend(board.cards.iter().copied());
+/// let unseen = CardSet::full().difference(known);
+/// for card in unseen {
+///     println!("{}", card);
+/// }
+/// '''
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CardSet {
+    pub bits: u64,
+}
+
+impl CardSet {
+    pub fn new() -> Self {
+        CardSet { bits: 0 }
+    }
+
+    // All 52 cards
+    pub fn full() -> Self {
+        CardSet { bits: FULL_DECK }
+    }
+
+    // Adds a card, returns false if it already was in the set
+    pub fn insert(&mut self, card: Card) -> bool {
+        let was_in = self.contains(card);
+        self.bits |= bit(card);
+        !was_in
+    }
+
+    // Removes a card, returns false if it wasn't in the set
+    pub fn remove(&mut self, card: Card) -> bool {
+        let was_in = self.contains(card);
+        self.bits &= !bit(card);
+        was_in
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.bits & bit(card) != 0
+    }
+
+    pub fn union(&self, other: CardSet) -> CardSet {
+        CardSet { bits: self.bits | other.bits }
+    }
+
+    pub fn intersection(&self, other: CardSet) -> CardSet {
+        CardSet { bits: self.bits & other.bits }
+    }
+
+    // The cards in this set that aren't in 'other'
+    pub fn difference(&self, other: CardSet) -> CardSet {
+        CardSet { bits: self.bits & !other.bits }
+    }
+
+    pub fn is_disjoint(&self, other: CardSet) -> bool {
+        self.bits & other.bits == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    // The cards in the set, lowest Card::as_index() first
+    pub fn iter(&self) -> CardSetIter {
+        CardSetIter { bits: self.bits }
+    }
+}
+
+// Bit for a card in the mask
+fn bit(card: Card) -> u64 {
+    1 << card.as_index()
+}
+
+pub struct CardSetIter {
+    bits: u64,
+}
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.bits == 0 {
+            return None;
+        }
+        let index = self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1; // Clear the lowest bit
+        Card::from_index(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bits.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut set = CardSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, iter: I) {
+        for card in iter {
+            self.insert(card);
+        }
+    }
+}
+
+impl From<&[Card]> for CardSet {
+    fn from(cards: &[Card]) -> Self {
+        cards.iter().copied().collect()
+    }
+}
+
+// The cards next to each other, e.g. "2c7dAh"
+impl fmt::Display for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for card in self.iter() {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::card::parse_cards;
+
+    fn set(s: &str) -> CardSet {
+        CardSet::from(&parse_cards(s).unwrap()[..])
+    }
+
+    fn card(s: &str) -> Card {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn insert_and_remove_report_changes() {
+        let mut cards = CardSet::new();
+        assert!(cards.is_empty());
+        assert!(cards.insert(card("Ah")));
+        assert!(!cards.insert(card("Ah")));
+        assert!(cards.contains(card("Ah")));
+        assert!(!cards.contains(card("Ad")));
+        assert_eq!(cards.len(), 1);
+
+        assert!(!cards.remove(card("Kd")));
+        assert!(cards.remove(card("Ah")));
+        assert!(!cards.remove(card("Ah")));
+        assert!(!cards.contains(card("Ah")));
+        assert!(cards.is_empty());
+    }
+
+    #[test]
+    fn set_operations() {
+        let first = set("AhKhQh");
+        let second = set("QhJh2c");
+        assert_eq!(first.union(second), set("AhKhQhJh2c"));
+        assert_eq!(first.intersection(second), set("Qh"));
+        assert_eq!(first.difference(second), set("AhKh"));
+        assert_eq!(second.difference(first), set("Jh2c"));
+        assert!(!first.is_disjoint(second));
+        assert!(first.is_disjoint(set("2c3c")));
+        assert_eq!(first.union(second).len(), 5);
+    }
+
+    #[test]
+    fn full_deck() {
+        let full = CardSet::full();
+        assert_eq!(full.len(), 52);
+        assert_eq!(full.difference(set("AhAs")).len(), 50);
+        assert_eq!(full.iter().size_hint(), (52, Some(52)));
+        for (index, card) in full.iter().enumerate() {
+            assert_eq!(card.as_index(), index);
+        }
+    }
+
+    #[test]
+    fn iterates_lowest_index_first() {
+        let cards: Vec<Card> = set("Ah 2s Td 2c").into_iter().collect();
+        assert_eq!(cards, parse_cards("2c 2s Td Ah").unwrap());
+        assert_eq!(CardSet::new().iter().next(), None);
+    }
+
+    #[test]
+    fn collect_and_extend() {
+        let collected: CardSet = parse_cards("AhKh").unwrap().into_iter().collect();
+        assert_eq!(collected, set("KhAh"));
+
+        let mut extended = collected;
+        extended.extend(parse_cards("KhQh").unwrap());
+        assert_eq!(extended, set("AhKhQh"));
+        assert_eq!(extended.len(), 3);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(set("Ah7d2c").to_string(), "2c7dAh");
+        assert_eq!(CardSet::new().to_string(), "");
+    }
+}
@@ -4,6 +4,12 @@ pub struct Collection {
     pub cards: Vec<Card>,
 }
 
+impl Default for Collection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Collection {
     pub fn new() -> Self {
         Collection { cards: Vec::new() }
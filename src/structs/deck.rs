@@ -1,10 +1,21 @@
 use super::card::Card;
+use super::card_set::CardSet;
+use super::error::ActionError;
 use super::enums::{Rank, Suit};
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use strum::IntoEnumIterator;
 
 pub struct Deck {
     pub cards: Vec<Card>,
+    pub dead: CardSet, // Cards that have been drawn or removed since the last reset
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Deck {
@@ -12,11 +23,11 @@ impl Deck {
         let mut deck: Vec<Card> = Vec::new();
         for suit in Suit::iter() {
             for rank in Rank::iter() {
-                let card: Card = Card{ suit: suit, rank: rank };
+                let card: Card = Card{ suit, rank };
                 deck.push(card);
             }
         }
-        Deck{cards: deck}
+        Deck{cards: deck, dead: CardSet::new()}
     }
 
     /// Shuffles deck randomly.
@@ -26,7 +37,31 @@ impl Deck {
     /// - Will NOT reinitialize the deck to 52 cards. This can be done with the Deck.reset() function.
     pub fn shuffle(&mut self) {
         let mut rng = rand::rng();
-        self.cards.shuffle(&mut rng);
+        self.shuffle_with_rng(&mut rng);
+    }
+
+    /// Shuffles the deck from a seed, the same seed always gives the same order.
+    /// 
+    /// Behavior:
+    /// - Uses ChaCha20 so the order doesn't change between platforms or rand versions.
+    /// - Like shuffle() it only shuffles the cards currently in the deck, call Deck.reset() first for a full deck.
+    /// 
+    /// Example:
+    /// '''
+    /// let mut first = Deck::new();
+    /// let mut second = Deck::new();
+    /// first.shuffle_seeded(42);
+    /// second.shuffle_seeded(42);
+    /// assert_eq!(first.draw(5).unwrap(), second.draw(5).unwrap());
+    /// '''
+    pub fn shuffle_seeded(&mut self, seed: u64) {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        self.shuffle_with_rng(&mut rng);
+    }
+
+    // Shuffles the deck with the given random number generator
+    pub fn shuffle_with_rng<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
     }
 
     /// Draws the requested amount of cards and returns them as a Vector.
@@ -35,9 +70,9 @@ impl Deck {
     /// - 'amount': usize that dictates how many cards will be drawn
     /// 
     /// # Returns
-    /// - A Result<Vec<Card>, &´static str> where:
+    /// - A Result<Vec<Card>, ActionError> where:
     ///     - Vec<Card>: is a vector containing the cards that were drawn from the deck.
-    ///     - &´static string: is the error in case there are less cards in the deck than what was requested to draw.
+    ///     - ActionError: is DeckExhausted in case there are less cards in the deck than what was requested to draw.
     /// 
     /// Example:
     /// '''
@@ -55,25 +90,38 @@ impl Deck {
     ///     }
     /// }
     /// '''
-    pub fn draw(&mut self, amount: usize) -> Result<Vec<Card>, &'static str> {
+    pub fn draw(&mut self, amount: usize) -> Result<Vec<Card>, ActionError> {
         if self.cards.len() < amount {
-            return Err("Not enough cards in deck");
+            return Err(ActionError::DeckExhausted);
         }
         let drawn_cards: Vec<Card> = (0..amount)
             .filter_map(|_| self.cards.pop())
             .collect();
+        self.dead.extend(drawn_cards.iter().copied());
         Ok(drawn_cards)
     }
 
+    // Takes the given cards out of the deck and marks them as dead, e.g. cards that are already known in a simulation
+    pub fn remove(&mut self, cards: CardSet) {
+        self.cards.retain(|card| !cards.contains(*card));
+        self.dead = self.dead.union(cards);
+    }
+
+    // The cards still in the deck
+    pub fn remaining(&self) -> CardSet {
+        CardSet::from(&self.cards[..])
+    }
+
     /// Resets the deck, making it sorted (like buying a new playing card deck).
     pub fn reset(&mut self) {
         let mut deck: Vec<Card> = Vec::new();
         for suit in Suit::iter() {
             for rank in Rank::iter() {
-                let card: Card = Card{ suit: suit, rank: rank };
+                let card: Card = Card{ suit, rank };
                 deck.push(card);
             }
         }
         self.cards = deck;
+        self.dead = CardSet::new();
     }
 }
\ No newline at end of file
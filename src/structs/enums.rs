@@ -1,6 +1,8 @@
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, EnumIter, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Suit {
     Spades,
     Clubs,
@@ -8,9 +10,9 @@ pub enum Suit {
     Diamonds,
 }
 
-#[derive(Clone, Copy, Debug, EnumIter, PartialEq, Eq)]
+// Ordered from lowest to highest with the ace high, use ace_low_value() where the ace counts as one (the wheel, A-2-3-4-5)
+#[derive(Clone, Copy, Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Rank {
-    Ace,
     Two,
     Three,
     Four,
@@ -23,9 +25,33 @@ pub enum Rank {
     Jack,
     Queen,
     King,
+    Ace,
 }
 
 impl Rank {
+    // Position from 0 (two) to 12 (ace)
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    // The rank at a position from 0 (two) to 12 (ace), None if out of range
+    pub fn from_index(index: usize) -> Option<Rank> {
+        Rank::iter().nth(index)
+    }
+
+    // Value of the rank as on the card, 2-10 for number cards, then 11 (jack), 12 (queen), 13 (king) and 14 (ace)
+    pub fn value(&self) -> u8 {
+        *self as u8 + 2
+    }
+
+    // Same as value() but the ace is 1, as in a wheel straight
+    pub fn ace_low_value(&self) -> u8 {
+        match self {
+            Rank::Ace => 1,
+            _ => self.value(),
+        }
+    }
+
     pub fn to_char(&self) -> char {
         match self {
             Rank::Ace => 'A',
@@ -43,4 +69,164 @@ impl Rank {
             Rank::King => 'K',
         }
     }
-}
\ No newline at end of file
+
+    // The rank for a notation character, e.g. 'T' => Ten. Lowercase letters are accepted too
+    pub fn from_char(c: char) -> Option<Rank> {
+        match c.to_ascii_uppercase() {
+            'A' => Some(Rank::Ace),
+            '2' => Some(Rank::Two),
+            '3' => Some(Rank::Three),
+            '4' => Some(Rank::Four),
+            '5' => Some(Rank::Five),
+            '6' => Some(Rank::Six),
+            '7' => Some(Rank::Seven),
+            '8' => Some(Rank::Eight),
+            '9' => Some(Rank::Nine),
+            'T' => Some(Rank::Ten),
+            'J' => Some(Rank::Jack),
+            'Q' => Some(Rank::Queen),
+            'K' => Some(Rank::King),
+            _ => None,
+        }
+    }
+}
+
+impl Suit {
+    pub fn to_char(&self) -> char {
+        match self {
+            Suit::Spades => 's',
+            Suit::Clubs => 'c',
+            Suit::Hearts => 'h',
+            Suit::Diamonds => 'd',
+        }
+    }
+
+    // The suit for a notation character, e.g. 'h' => Hearts. Uppercase letters are accepted too
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c.to_ascii_lowercase() {
+            's' => Some(Suit::Spades),
+            'c' => Some(Suit::Clubs),
+            'h' => Some(Suit::Hearts),
+            'd' => Some(Suit::Diamonds),
+            _ => None,
+        }
+    }
+}
+
+// The streets of a hand of Texas Hold'em, in the order they are played
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    Showdown,
+}
+
+// Every action a player can take on their turn. Bet and RaiseTo hold the player's total bet for the round
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    Bet(u32),
+    RaiseTo(u32),
+    AllIn,
+}
+
+// The type of a poker hand, ordered from worst to best
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HandCategory {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+impl HandCategory {
+    // The category of an evaluated hand rank (0-7461, higher is better). None if the rank is out of range
+    pub fn from_rank(rank: u32) -> Option<HandCategory> {
+        match rank {
+            7452..=7461 => Some(HandCategory::StraightFlush),
+            7296..=7451 => Some(HandCategory::FourOfAKind),
+            7140..=7295 => Some(HandCategory::FullHouse),
+            5863..=7139 => Some(HandCategory::Flush),
+            5853..=5862 => Some(HandCategory::Straight),
+            4995..=5852 => Some(HandCategory::ThreeOfAKind),
+            4137..=4994 => Some(HandCategory::TwoPair),
+            1277..=4136 => Some(HandCategory::OnePair),
+            0..=1276    => Some(HandCategory::HighCard),
+            _ => None,
+        }
+    }
+
+    pub fn to_words(&self) -> &'static str {
+        match self {
+            HandCategory::StraightFlush => "Straight Flush",
+            HandCategory::FourOfAKind => "Four of a Kind",
+            HandCategory::FullHouse => "Full House",
+            HandCategory::Flush => "Flush",
+            HandCategory::Straight => "Straight",
+            HandCategory::ThreeOfAKind => "Three of a Kind",
+            HandCategory::TwoPair => "Two Pair",
+            HandCategory::OnePair => "One Pair",
+            HandCategory::HighCard => "High Card",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::card::{parse_cards, Card};
+    use crate::structs::deck::Deck;
+
+    #[test]
+    fn ranks_are_ace_high() {
+        let ranks: Vec<Rank> = Rank::iter().collect();
+        assert_eq!(ranks.len(), 13);
+        assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ranks.first(), Some(&Rank::Two));
+        assert_eq!(ranks.last(), Some(&Rank::Ace));
+        assert!(Rank::King < Rank::Ace && Rank::Two < Rank::Three);
+        assert_eq!(Rank::iter().max(), Some(Rank::Ace));
+    }
+
+    #[test]
+    fn index_and_value() {
+        for (i, rank) in Rank::iter().enumerate() {
+            assert_eq!(rank.index(), i);
+            assert_eq!(Rank::from_index(rank.index()), Some(rank));
+            assert_eq!(rank.value() as usize, i + 2);
+            assert_eq!(Rank::from_char(rank.to_char()), Some(rank));
+        }
+        assert_eq!(Rank::from_index(13), None);
+        assert_eq!(Rank::Ace.value(), 14);
+        assert_eq!(Rank::Ace.ace_low_value(), 1);
+        assert_eq!(Rank::King.ace_low_value(), 13);
+        assert_eq!(Rank::Two.ace_low_value(), 2);
+    }
+
+    #[test]
+    fn sorting_cards_matches_card_ord() {
+        let mut deck = Deck::new();
+        deck.shuffle_seeded(11);
+        let mut sorted = deck.cards.clone();
+        sorted.sort();
+
+        let mut by_hand = deck.cards.clone();
+        by_hand.sort_by_key(|card| (card.rank.value(), card.suit));
+        assert_eq!(sorted, by_hand);
+        assert!(sorted.windows(2).all(|pair| pair[0].cmp(&pair[1]) == std::cmp::Ordering::Less));
+
+        let mut cards = parse_cards("Ah2c Kd Ts As 2s").unwrap();
+        cards.sort();
+        assert_eq!(cards, parse_cards("2s 2c Ts Kd As Ah").unwrap());
+        assert!("Ah".parse::<Card>().unwrap() > "Kh".parse::<Card>().unwrap());
+    }
+}
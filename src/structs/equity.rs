@@ -0,0 +1,442 @@
+use super::board::Board;
+use super::card::Card;
+use super::card_set::CardSet;
+use super::error::EquityError;
+use super::evaluator::Evaluator;
+use super::hand::Hand;
+use super::range::Range;
+use itertools::Itertools;
+use rand::distr::weighted::WeightedIndex;
+use rand::distr::Distribution;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+const MAX_FAILED_SAMPLES: u32 = 100_000; // Monte Carlo for ranges gives up after this many picks in a row where combos share a card
+
+// How the equity is calculated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EquitySettings {
+    pub max_exact_boards: u64, // Enumerate every board when there are at most this many, otherwise use Monte Carlo
+    pub samples: u64, // Number of random boards for Monte Carlo
+    pub seed: Option<u64>, // Seed for Monte Carlo so a result can be reproduced, a random one is used if None
+}
+
+impl Default for EquitySettings {
+    fn default() -> Self {
+        EquitySettings { max_exact_boards: 100_000, samples: 100_000, seed: None }
+    }
+}
+
+// One player's odds, in percent (0-100)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerEquity {
+    pub win: f64, // Boards where the player wins alone
+    pub tie: f64, // Boards where the player splits the pot
+    pub lose: f64,
+    pub equity: f64, // Share of the pot on average, wins plus the player's part of every split
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equity {
+    pub players: Vec<PlayerEquity>, // In the same order as the hands that were given
+    pub boards: u64, // Number of boards that were evaluated
+    pub exact: bool, // True if every possible board was evaluated, false for Monte Carlo
+}
+
+/// Calculates every player's chance to win, tie or lose from the known hole cards and board.
+///
+/// # Parameters
+/// - 'hands': The hole cards of every player still in the hand, at least two players with two cards each.
+/// - 'board': The board so far, 0-5 cards.
+/// - 'dead': Cards that are known to be out of the deck, e.g. folded or burnt cards. Can be empty.
+/// - 'settings': When to enumerate and how many samples to use otherwise, see EquitySettings.
+///
+/// # Returns
+/// - A Result<Equity, EquityError> where:
+///     - Equity: win/tie/lose percentages for every player, and whether they are exact.
+///     - EquityError: if there are too few players, a hand or the board has the wrong amount of cards,
+///       a card is used twice or there are not enough cards left to finish the board.
+///       NoSamples if Monte Carlo is needed and settings.samples is 0.
+///
+/// # Behavior
+/// - Every possible way to finish the board is evaluated if there are at most settings.max_exact_boards of them.
+/// - Otherwise settings.samples random boards are evaluated (Monte Carlo).
+///
+/// # Example
+/// '''
+/// let hands: Vec<Hand> = vec!["AhAs".parse()?, "KdKc".parse()?];
+/// let board: Board = "2c7h9s".parse()?;
+/// let equity = calculate_equity(&hands, &board, CardSet::new(), EquitySettings::default())?;
+/// println!("Aces win {:.1}% of the time", equity.players[0].win);
+/// '''
+pub fn calculate_equity(hands: &[Hand], board: &Board, dead: CardSet, settings: EquitySettings) -> Result<Equity, EquityError> {
+    if hands.len() < 2 {
+        return Err(EquityError::NotEnoughPlayers);
+    }
+    if board.cards.len() > 5 {
+        return Err(EquityError::TooManyBoardCards(board.cards.len()));
+    }
+
+    // Every known card may only be used once
+    let mut known = dead;
+    let mut hole_cards: Vec<CardSet> = Vec::new();
+    for (player, hand) in hands.iter().enumerate() {
+        if hand.cards.len() != 2 {
+            return Err(EquityError::InvalidHand { player, cards: hand.cards.len() });
+        }
+        hole_cards.push(add_known(&mut known, &hand.cards)?);
+    }
+    let board_cards = add_known(&mut known, &board.cards)?;
+
+    let remaining: Vec<Card> = CardSet::full().difference(known).iter().collect();
+    let missing = 5 - board.cards.len();
+    if remaining.len() < missing {
+        return Err(EquityError::NotEnoughCards);
+    }
+
+    let mut tally = Tally::new(hands.len());
+    let exact = binomial(remaining.len() as u64, missing as u64) <= settings.max_exact_boards;
+    if exact {
+        for runout in remaining.iter().copied().combinations(missing) {
+            tally.add(&hole_cards, board_cards.union(CardSet::from(&runout[..])), 1.0);
+        }
+    } else {
+        if settings.samples == 0 {
+            return Err(EquityError::NoSamples);
+        }
+        let mut rng = match settings.seed {
+            Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+            None => ChaCha20Rng::from_rng(&mut rand::rng()),
+        };
+        monte_carlo(&mut tally, &hole_cards, board_cards, remaining, missing, settings.samples, &mut rng);
+    }
+
+    Ok(Equity { players: tally.percentages(), boards: tally.boards, exact })
+}
+
+/// Calculates the equity of ranges against each other, e.g. "QQ+, AKs" against "22+, A2s+, KTs+".
+///
+/// # Parameters
+/// - 'ranges': One range per player, at least two. Use Range::from(&hand) for a player with a known hand.
+/// - 'board': The board so far, 0-5 cards.
+/// - 'dead': Cards that are known to be out of the deck. Can be empty.
+/// - 'settings': When to enumerate and how many samples to use otherwise, see EquitySettings.
+///
+/// # Returns
+/// - A Result<Equity, EquityError> where:
+///     - Equity: win/tie/lose percentages for every player, every combo counted by its weight.
+///     - EquityError: like calculate_equity (NoSamples included), and also EmptyRange if a range has no combos left after removing
+///       the board and dead cards, or NoValidCombos if the ranges can't be dealt without sharing a card.
+///
+/// # Behavior
+/// - Combos that use a board or dead card are removed from the ranges first (card removal).
+/// - Combinations of combos that share a card are skipped.
+/// - Everything is enumerated if the combos times the boards are at most settings.max_exact_boards,
+///   otherwise combos are picked by their weights and boards at random for settings.samples boards.
+///
+/// # Example
+/// '''
+/// let hero: Hand = "AhKh".parse()?;
+/// let villain: Range = "QQ+, AKs".parse()?;
+/// let equity = range_equity(&[Range::from(&hero), villain], &Board::new(), CardSet::new(), EquitySettings::default())?;
+/// println!("AhKh has {:.1}% equity", equity.players[0].equity);
+/// '''
+pub fn range_equity(ranges: &[Range], board: &Board, dead: CardSet, settings: EquitySettings) -> Result<Equity, EquityError> {
+    if ranges.len() < 2 {
+        return Err(EquityError::NotEnoughPlayers);
+    }
+    if board.cards.len() > 5 {
+        return Err(EquityError::TooManyBoardCards(board.cards.len()));
+    }
+
+    let mut known = dead;
+    let board_cards = add_known(&mut known, &board.cards)?;
+    let ranges: Vec<Range> = ranges.iter().map(|range| range.without(known)).collect();
+    if let Some(player) = ranges.iter().position(Range::is_empty) {
+        return Err(EquityError::EmptyRange(player));
+    }
+
+    let missing = 5 - board.cards.len();
+    let cards_left = 52 - known.len();
+    if cards_left < missing + 2 * ranges.len() {
+        return Err(EquityError::NotEnoughCards);
+    }
+
+    let combos = ranges.iter().fold(1u64, |product, range| product.saturating_mul(range.len() as u64));
+    let boards = binomial((cards_left - 2 * ranges.len()) as u64, missing as u64);
+    let exact = combos.saturating_mul(boards) <= settings.max_exact_boards;
+
+    let mut tally = Tally::new(ranges.len());
+    if exact {
+        for picked in ranges.iter().map(|range| range.combos.iter()).multi_cartesian_product() {
+            let hole_cards: Vec<CardSet> = picked.iter().map(|combo| combo.card_set()).collect();
+            let Some(used) = deal_together(known, &hole_cards) else {
+                continue;
+            };
+            let weight: f64 = picked.iter().map(|combo| combo.weight).product();
+            let remaining: Vec<Card> = CardSet::full().difference(used).iter().collect();
+            for runout in remaining.iter().copied().combinations(missing) {
+                tally.add(&hole_cards, board_cards.union(CardSet::from(&runout[..])), weight);
+            }
+        }
+    } else {
+        if settings.samples == 0 {
+            return Err(EquityError::NoSamples);
+        }
+        let mut rng = match settings.seed {
+            Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+            None => ChaCha20Rng::from_rng(&mut rand::rng()),
+        };
+        range_monte_carlo(&mut tally, &ranges, known, board_cards, missing, settings.samples, &mut rng)?;
+    }
+
+    if tally.boards == 0 {
+        return Err(EquityError::NoValidCombos);
+    }
+    Ok(Equity { players: tally.percentages(), boards: tally.boards, exact })
+}
+
+// Equity of one known hand against a range, the hand is players[0] in the result
+pub fn hand_vs_range(hand: &Hand, range: &Range, board: &Board, dead: CardSet, settings: EquitySettings) -> Result<Equity, EquityError> {
+    range_equity(&[Range::from(hand), range.clone()], board, dead, settings)
+}
+
+// Picks combos by their weights and a random board until 'samples' boards are done. Picks where two combos share a card are thrown away
+fn range_monte_carlo<R: Rng>(
+    tally: &mut Tally,
+    ranges: &[Range],
+    known: CardSet,
+    board_cards: CardSet,
+    missing: usize,
+    samples: u64,
+    rng: &mut R,
+) -> Result<(), EquityError> {
+    let mut pickers = Vec::new();
+    for (player, range) in ranges.iter().enumerate() {
+        let picker = WeightedIndex::new(range.combos.iter().map(|combo| combo.weight))
+            .map_err(|_| EquityError::EmptyRange(player))?;
+        pickers.push(picker);
+    }
+
+    let mut failed_in_a_row = 0;
+    while tally.boards < samples {
+        let hole_cards: Vec<CardSet> = ranges
+            .iter()
+            .zip(&pickers)
+            .map(|(range, picker)| range.combos[picker.sample(rng)].card_set())
+            .collect();
+        let Some(used) = deal_together(known, &hole_cards) else {
+            failed_in_a_row += 1;
+            if failed_in_a_row >= MAX_FAILED_SAMPLES {
+                return Err(EquityError::NoValidCombos);
+            }
+            continue;
+        };
+        failed_in_a_row = 0;
+
+        let mut remaining: Vec<Card> = CardSet::full().difference(used).iter().collect();
+        let (runout, _) = remaining.partial_shuffle(rng, missing);
+        tally.add(&hole_cards, board_cards.union(CardSet::from(&*runout)), 1.0);
+    }
+    Ok(())
+}
+
+// Every card in use once the hole cards are dealt next to the known cards, None if two of them share a card
+fn deal_together(known: CardSet, hole_cards: &[CardSet]) -> Option<CardSet> {
+    let mut used = known;
+    for hole in hole_cards {
+        if !used.is_disjoint(*hole) {
+            return None;
+        }
+        used = used.union(*hole);
+    }
+    Some(used)
+}
+
+// Evaluates 'samples' random ways to finish the board
+fn monte_carlo<R: Rng>(
+    tally: &mut Tally,
+    hole_cards: &[CardSet],
+    board_cards: CardSet,
+    mut remaining: Vec<Card>,
+    missing: usize,
+    samples: u64,
+    rng: &mut R,
+) {
+    for _ in 0..samples {
+        let (runout, _) = remaining.partial_shuffle(rng, missing);
+        tally.add(hole_cards, board_cards.union(CardSet::from(&*runout)), 1.0);
+    }
+}
+
+// Weighted wins, ties and pot shares for every player over the boards evaluated so far
+struct Tally {
+    wins: Vec<f64>,
+    ties: Vec<f64>,
+    shares: Vec<f64>,
+    total_weight: f64,
+    boards: u64,
+}
+
+impl Tally {
+    fn new(players: usize) -> Self {
+        Tally { wins: vec![0.0; players], ties: vec![0.0; players], shares: vec![0.0; players], total_weight: 0.0, boards: 0 }
+    }
+
+    // Evaluates one complete board for every player and counts who wins, 'weight' is how much the board counts
+    fn add(&mut self, hole_cards: &[CardSet], board: CardSet, weight: f64) {
+        let evaluator = Evaluator::shared();
+        let ranks: Vec<u32> = hole_cards
+            .iter()
+            .map(|hole| evaluator.evaluate_set(hole.union(board)).unwrap_or(0))
+            .collect();
+
+        let best = ranks.iter().copied().max().unwrap_or(0);
+        let winners: Vec<usize> = (0..ranks.len()).filter(|&i| ranks[i] == best).collect();
+        for &winner in &winners {
+            if winners.len() == 1 {
+                self.wins[winner] += weight;
+            } else {
+                self.ties[winner] += weight;
+            }
+            self.shares[winner] += weight / winners.len() as f64;
+        }
+        self.total_weight += weight;
+        self.boards += 1;
+    }
+
+    fn percentages(&self) -> Vec<PlayerEquity> {
+        if self.total_weight == 0.0 {
+            return vec![PlayerEquity::default(); self.wins.len()];
+        }
+        let total = self.total_weight;
+        (0..self.wins.len())
+            .map(|i| {
+                let win = self.wins[i] / total * 100.0;
+                let tie = self.ties[i] / total * 100.0;
+                PlayerEquity { win, tie, lose: 100.0 - win - tie, equity: self.shares[i] / total * 100.0 }
+            })
+            .collect()
+    }
+}
+
+// Adds cards to the known cards, fails if one of them is already known
+fn add_known(known: &mut CardSet, cards: &[Card]) -> Result<CardSet, EquityError> {
+    let mut added = CardSet::new();
+    for &card in cards {
+        if !known.insert(card) {
+            return Err(EquityError::DuplicateCard(card.to_string()));
+        }
+        added.insert(card);
+    }
+    Ok(added)
+}
+
+fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::card::parse_cards;
+
+    fn hands(hands: &[&str]) -> Vec<Hand> {
+        hands.iter().map(|hand| hand.parse().unwrap()).collect()
+    }
+
+    fn monte_carlo_settings(samples: u64) -> EquitySettings {
+        EquitySettings { max_exact_boards: 0, samples, seed: Some(7) }
+    }
+
+    fn assert_close(actual: f64, expected: f64, margin: f64) {
+        assert!((actual - expected).abs() <= margin, "{} is not within {} of {}", actual, margin, expected);
+    }
+
+    #[test]
+    fn aces_against_kings_preflop_exact() {
+        let settings = EquitySettings { max_exact_boards: u64::MAX, ..EquitySettings::default() };
+        let equity = calculate_equity(&hands(&["AhAs", "KdKc"]), &Board::new(), CardSet::new(), settings).unwrap();
+
+        assert!(equity.exact);
+        assert_eq!(equity.boards, 1_712_304);
+        assert_close(equity.players[0].win, 81.06, 0.005);
+        assert_close(equity.players[0].tie, 0.38, 0.005);
+        assert_close(equity.players[1].win, 18.55, 0.005);
+        assert_close(equity.players[0].equity + equity.players[1].equity, 100.0, 1e-9);
+    }
+
+    #[test]
+    fn monte_carlo_is_close_and_reproducible() {
+        let aces_kings = hands(&["AhAs", "KdKc"]);
+        let equity = calculate_equity(&aces_kings, &Board::new(), CardSet::new(), monte_carlo_settings(20_000)).unwrap();
+        assert!(!equity.exact);
+        assert_eq!(equity.boards, 20_000);
+        assert_close(equity.players[0].win, 81.06, 1.5);
+
+        let again = calculate_equity(&aces_kings, &Board::new(), CardSet::new(), monte_carlo_settings(20_000)).unwrap();
+        assert_eq!(again, equity);
+    }
+
+    #[test]
+    fn known_boards() {
+        let river: Board = "Kh7c2d3s9h".parse().unwrap();
+        let equity = calculate_equity(&hands(&["AhAs", "KdKc"]), &river, CardSet::new(), EquitySettings::default()).unwrap();
+        assert_eq!(equity.boards, 1);
+        assert_eq!(equity.players[1].win, 100.0);
+
+        // Both play the broadway straight on the board
+        let broadway: Board = "AcKhQdJsTc".parse().unwrap();
+        let equity = calculate_equity(&hands(&["2h3h", "4d5d"]), &broadway, CardSet::new(), EquitySettings::default()).unwrap();
+        assert_eq!(equity.players[0].tie, 100.0);
+        assert_eq!(equity.players[1].equity, 50.0);
+    }
+
+    #[test]
+    fn zero_samples_are_rejected() {
+        let aces_kings = hands(&["AhAs", "KdKc"]);
+        assert_eq!(calculate_equity(&aces_kings, &Board::new(), CardSet::new(), monte_carlo_settings(0)), Err(EquityError::NoSamples));
+
+        let ranges: Vec<Range> = vec!["QQ+".parse().unwrap(), "AKs".parse().unwrap()];
+        assert_eq!(range_equity(&ranges, &Board::new(), CardSet::new(), monte_carlo_settings(0)), Err(EquityError::NoSamples));
+
+        // Enumeration doesn't need samples
+        let river: Board = "Kh7c2d3s9h".parse().unwrap();
+        let exact = EquitySettings { samples: 0, ..EquitySettings::default() };
+        assert!(calculate_equity(&aces_kings, &river, CardSet::new(), exact).is_ok());
+    }
+
+    #[test]
+    fn invalid_input() {
+        let board = Board::new();
+        let settings = EquitySettings::default();
+        assert_eq!(calculate_equity(&hands(&["AhAs"]), &board, CardSet::new(), settings), Err(EquityError::NotEnoughPlayers));
+        assert_eq!(calculate_equity(&hands(&["AhAs", "AhKc"]), &board, CardSet::new(), settings), Err(EquityError::DuplicateCard("Ah".to_string())));
+
+        let short = vec!["AhAs".parse().unwrap(), Hand { cards: parse_cards("Kd").unwrap() }];
+        assert_eq!(calculate_equity(&short, &board, CardSet::new(), settings), Err(EquityError::InvalidHand { player: 1, cards: 1 }));
+
+        let full_board = Board { cards: parse_cards("2c3c4c5c6c7c").unwrap() };
+        assert_eq!(calculate_equity(&hands(&["AhAs", "KdKc"]), &full_board, CardSet::new(), settings), Err(EquityError::TooManyBoardCards(6)));
+    }
+
+    #[test]
+    fn hand_against_a_range() {
+        let hero: Hand = "AhAs".parse().unwrap();
+        let villain: Range = "KK".parse().unwrap();
+        let board: Board = "2c7h9s".parse().unwrap();
+        let equity = hand_vs_range(&hero, &villain, &board, CardSet::new(), EquitySettings::default()).unwrap();
+        assert!(equity.exact);
+        assert_close(equity.players[0].equity + equity.players[1].equity, 100.0, 1e-9);
+        assert!(equity.players[0].equity > 90.0);
+
+        // Every king combo uses a dead card
+        let dead = CardSet::from(&parse_cards("KsKh").unwrap()[..]);
+        let villain: Range = "KsKh".parse().unwrap();
+        assert_eq!(hand_vs_range(&hero, &villain, &board, dead, EquitySettings::default()), Err(EquityError::EmptyRange(1)));
+    }
+}
@@ -0,0 +1,109 @@
+use thiserror::Error;
+
+// Everything that can go wrong when a player acts or the game moves on, so frontends and bots can react to each case
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum ActionError {
+    #[error("It's not this player's turn")]
+    NotYourTurn,
+    #[error("Not enough chips: needs {needed} but has {available}")]
+    InsufficientChips { needed: u32, available: u32 },
+    #[error("Player doesn't have any chips")]
+    NoChipsLeft,
+    #[error("Bet must be above the current bet of {current_bet}(or use call)")]
+    BetBelowCurrent { current_bet: u32 },
+    #[error("Raise must be at least the minimum raise of {min_raise}(or go all in)")]
+    RaiseBelowMinimum { min_raise: u32 },
+    #[error("There already is a bet, use raise instead")]
+    BetAlreadyMade,
+    #[error("Cannot check: need to call, raise or fold")]
+    CannotCheck,
+    #[error("Action was not reopened, only call or fold")]
+    ActionNotReopened,
+    #[error("The hand is over")]
+    HandOver,
+    #[error("The betting round is not over")]
+    BettingRoundNotOver,
+    #[error("The betting round is over, the next street has to be dealt first")]
+    BettingRoundOver,
+    #[error("Player has folded")]
+    PlayerFolded,
+    #[error("Player is already all in")]
+    PlayerAllIn,
+    #[error("Not enough players with chips to start a hand")]
+    NotEnoughPlayers,
+    #[error("Not enough cards in deck")]
+    DeckExhausted,
+}
+
+// Reasons a provably fair deal fails verification, see fairness::verify
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum FairnessError {
+    #[error("The revealed seed doesn't match the commitment")]
+    CommitmentMismatch,
+    #[error("The card at deck position {position} wasn't dealt from the committed seed")]
+    DealMismatch { position: usize },
+}
+
+// Reasons a card, hand or board in text notation (e.g. "AhKh QsJs9d") can't be parsed
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ParseCardError {
+    #[error("'{0}' is not a rank (use A, K, Q, J, T or 2-9)")]
+    InvalidRank(char),
+    #[error("'{0}' is not a suit (use s, c, h or d)")]
+    InvalidSuit(char),
+    #[error("'{0}' is missing a suit")]
+    MissingSuit(char),
+    #[error("Expected {expected} cards but found {found}")]
+    WrongCardCount { expected: String, found: usize },
+    #[error("{0} is there more than once")]
+    DuplicateCard(String),
+}
+
+// Reasons an equity calculation can't be done, see equity::calculate_equity
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum EquityError {
+    #[error("Need at least two players")]
+    NotEnoughPlayers,
+    #[error("Player {player} has {cards} cards, a hand is two cards")]
+    InvalidHand { player: usize, cards: usize },
+    #[error("The board has {0} cards, it can't have more than five")]
+    TooManyBoardCards(usize),
+    #[error("{0} is used more than once")]
+    DuplicateCard(String),
+    #[error("Not enough cards left in the deck to finish the board")]
+    NotEnoughCards,
+    #[error("Player {0}'s range has no combos left after card removal")]
+    EmptyRange(usize),
+    #[error("The ranges can't be dealt without two players sharing a card")]
+    NoValidCombos,
+    #[error("Monte Carlo needs at least one sample")]
+    NoSamples,
+}
+
+// Reasons a range in text notation (e.g. "QQ+, AKs, A5s-A2s") can't be parsed
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ParseRangeError {
+    #[error("'{0}' is not a valid part of a range")]
+    InvalidPart(String),
+    #[error("'{0}' is not a valid weight, use a number above 0 and at most 1")]
+    InvalidWeight(String),
+}
+
+// Reasons a message can't be sent or received, see protocol
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error("Connection error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The connection was closed")]
+    ConnectionClosed,
+    #[error("Message of {0} bytes is too large")]
+    FrameTooLarge(usize),
+    #[error("Invalid message: {0}")]
+    InvalidMessage(#[from] serde_json::Error),
+    #[error("Protocol version {theirs} is not supported, this side uses version {ours}")]
+    VersionMismatch { ours: u32, theirs: u32 },
+    #[error("Expected {0} first")]
+    UnexpectedMessage(&'static str),
+    #[error("The server refused the connection: {0}")]
+    Rejected(String),
+}
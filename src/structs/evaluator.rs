@@ -0,0 +1,245 @@
+use super::card::Card;
+use super::card_set::CardSet;
+use std::sync::{Arc, OnceLock};
+
+// Where each hand category starts in the rank scale (0-7461, higher => better hand)
+const HIGH_CARD: u32 = 0;
+const ONE_PAIR: u32 = 1277;
+const TWO_PAIR: u32 = 4137;
+const THREE_OF_A_KIND: u32 = 4995;
+const STRAIGHT: u32 = 5853;
+const FLUSH: u32 = 5863;
+const FULL_HOUSE: u32 = 7140;
+const FOUR_OF_A_KIND: u32 = 7296;
+const STRAIGHT_FLUSH: u32 = 7452;
+
+const WHEEL: u16 = 0b1_0000_0000_1111; // A-2-3-4-5, the ace counts as low
+
+/// Hand evaluator working directly on Card, giving every hand a rank from 0 (7-5-4-3-2) to 7461 (royal flush).
+///
+/// Hands are evaluated from rank bitmasks (one bit per rank, two is bit 0 and ace is bit 12). Every category
+/// is ranked by its combination first and its kickers after, the same way the hands are compared at the table.
+/// The lookup tables are built once, use Evaluator::shared() to get the evaluator used by every game in the process.
+///
+/// # Example
+/// '''
+/// let evaluator = Evaluator::shared();
+/// if let Some((rank, best_five)) = evaluator.evaluate(&cards) {
+///     println!("Rank: {}, best five cards: {:?}", rank, best_five);
+/// }
+/// '''
+#[derive(Debug)]
+pub struct Evaluator {
+    high_card: Vec<u16>, // Rank of five different ranks without straight, indexed by rank bitmask
+    straight: Vec<i8>, // Highest straight in a rank bitmask, 0 = wheel and 9 = ace high, -1 = no straight
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        let mut straight = vec![-1; 1 << 13];
+        for (mask, entry) in straight.iter_mut().enumerate() {
+            *entry = highest_straight(mask as u16);
+        }
+
+        // Bitmasks compare like high card hands do, so counting them in order ranks them
+        let mut high_card = vec![0; 1 << 13];
+        let mut next_rank = 0;
+        for mask in 0..(1u16 << 13) {
+            if mask.count_ones() == 5 && straight[mask as usize] < 0 {
+                high_card[mask as usize] = next_rank;
+                next_rank += 1;
+            }
+        }
+
+        Evaluator { high_card, straight }
+    }
+
+    // The process-wide evaluator, built the first time it's asked for and shared by every Game and Hand after that
+    pub fn shared() -> Arc<Evaluator> {
+        static SHARED: OnceLock<Arc<Evaluator>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Evaluator::new())).clone()
+    }
+
+    /// Evaluates five to seven cards.
+    ///
+    /// # Returns
+    /// - Some((rank, best_five)) where rank is 0-7461 (higher => better) and best_five are the cards that make the hand.
+    /// - None if there are fewer than five or more than seven different cards.
+    pub fn evaluate(&self, cards: &[Card]) -> Option<(u32, Vec<Card>)> {
+        let set = CardSet::from(cards);
+        if !(5..=7).contains(&set.len()) {
+            return None;
+        }
+        let (rank, ranks, suit) = self.classify(set);
+        Some((rank, pick_cards(cards, &ranks, suit)))
+    }
+
+    // Rank (0-7461) of five to seven cards without working out the best five, the fast path for simulations.
+    // None if the set doesn't have five to seven cards
+    pub fn evaluate_set(&self, cards: CardSet) -> Option<u32> {
+        if !(5..=7).contains(&cards.len()) {
+            return None;
+        }
+        Some(self.classify(cards).0)
+    }
+
+    // The rank of a hand together with the ranks of its best five cards (highest first, combination before kickers)
+    // and the suit they all have if it's a flush
+    fn classify(&self, cards: CardSet) -> (u32, Vec<usize>, Option<usize>) {
+        let mut rank_counts = [0u8; 13];
+        let mut suit_masks = [0u16; 4];
+        for card in cards {
+            let (rank, suit) = rank_and_suit(&card);
+            rank_counts[rank] += 1;
+            suit_masks[suit] |= 1 << rank;
+        }
+        let rank_mask = suit_masks.iter().fold(0, |mask, suit_mask| mask | suit_mask);
+        let ranks_with = |count: u8| -> Vec<usize> {
+            (0..13).rev().filter(|&rank| rank_counts[rank] >= count).collect()
+        };
+
+        // With at most seven cards a flush can't be beaten by four of a kind or a full house, so check it first
+        if let Some(suit) = (0..4).find(|&suit| suit_masks[suit].count_ones() >= 5) {
+            let suit_mask = suit_masks[suit];
+            let straight = self.straight[suit_mask as usize];
+            if straight >= 0 {
+                return (STRAIGHT_FLUSH + straight as u32, straight_ranks(straight), Some(suit));
+            }
+            let top_five = top_bits(suit_mask, 5);
+            return (FLUSH + self.high_card[top_five as usize] as u32, ranks_in(top_five), Some(suit));
+        }
+
+        let quads = ranks_with(4);
+        if let Some(&quad) = quads.first() {
+            let kicker = top_ranks(rank_mask & !(1 << quad), 1)[0];
+            let rank = FOUR_OF_A_KIND + quad as u32 * 12 + compress(kicker, &[quad]);
+            return (rank, vec![quad, quad, quad, quad, kicker], None);
+        }
+
+        let trips = ranks_with(3);
+        let pairs = ranks_with(2);
+        if let Some(&trip) = trips.first()
+            && let Some(&pair) = pairs.iter().find(|&&pair| pair != trip)
+        {
+            let rank = FULL_HOUSE + trip as u32 * 12 + compress(pair, &[trip]);
+            return (rank, vec![trip, trip, trip, pair, pair], None);
+        }
+
+        let straight = self.straight[rank_mask as usize];
+        if straight >= 0 {
+            return (STRAIGHT + straight as u32, straight_ranks(straight), None);
+        }
+
+        if let Some(&trip) = trips.first() {
+            let kickers = top_ranks(rank_mask & !(1 << trip), 2);
+            let kicker_rank = combination_rank(&[compress(kickers[1], &[trip]), compress(kickers[0], &[trip])]);
+            return (THREE_OF_A_KIND + trip as u32 * 66 + kicker_rank, vec![trip, trip, trip, kickers[0], kickers[1]], None);
+        }
+
+        if pairs.len() >= 2 {
+            let (high, low) = (pairs[0], pairs[1]);
+            let kicker = top_ranks(rank_mask & !(1 << high) & !(1 << low), 1)[0];
+            let pair_rank = combination_rank(&[low as u32, high as u32]);
+            let rank = TWO_PAIR + pair_rank * 11 + compress(kicker, &[high, low]);
+            return (rank, vec![high, high, low, low, kicker], None);
+        }
+
+        if let Some(&pair) = pairs.first() {
+            let kickers = top_ranks(rank_mask & !(1 << pair), 3);
+            let kicker_rank = combination_rank(&[
+                compress(kickers[2], &[pair]),
+                compress(kickers[1], &[pair]),
+                compress(kickers[0], &[pair]),
+            ]);
+            return (ONE_PAIR + pair as u32 * 220 + kicker_rank, vec![pair, pair, kickers[0], kickers[1], kickers[2]], None);
+        }
+
+        let top_five = top_bits(rank_mask, 5);
+        (HIGH_CARD + self.high_card[top_five as usize] as u32, ranks_in(top_five), None)
+    }
+}
+
+// Rank (0 = two, 12 = ace) and suit (0-3) of a card, from its index
+fn rank_and_suit(card: &Card) -> (usize, usize) {
+    let index = card.as_index();
+    (index / 4, index % 4)
+}
+
+// Highest straight in a rank bitmask, 0 = wheel and 9 = ace high, -1 = no straight
+fn highest_straight(mask: u16) -> i8 {
+    for high in (1..=9).rev() {
+        let straight = 0b1_1111 << (high - 1);
+        if mask & straight == straight {
+            return high as i8;
+        }
+    }
+    if mask & WHEEL == WHEEL { 0 } else { -1 }
+}
+
+// The five ranks of a straight, highest first. The wheel ends with the ace
+fn straight_ranks(straight: i8) -> Vec<usize> {
+    if straight == 0 {
+        return vec![3, 2, 1, 0, 12];
+    }
+    let high = straight as usize + 3;
+    (high - 4..=high).rev().collect()
+}
+
+// Keeps only the 'amount' highest bits of a bitmask
+fn top_bits(mask: u16, amount: usize) -> u16 {
+    top_ranks(mask, amount).iter().fold(0, |top, &rank| top | 1 << rank)
+}
+
+// The 'amount' highest ranks in a bitmask, highest first
+fn top_ranks(mask: u16, amount: usize) -> Vec<usize> {
+    (0..13).rev().filter(|&rank| mask & (1 << rank) != 0).take(amount).collect()
+}
+
+// Every rank in a bitmask, highest first
+fn ranks_in(mask: u16) -> Vec<usize> {
+    top_ranks(mask, 13)
+}
+
+// Position of a kicker among the ranks left when the ranks already used are removed
+fn compress(rank: usize, used: &[usize]) -> u32 {
+    (rank - used.iter().filter(|&&used_rank| used_rank < rank).count()) as u32
+}
+
+// Order of a set of different positions, given lowest first, among all sets of the same size.
+// Sets are ordered by their highest position, then the next highest and so on
+fn combination_rank(positions: &[u32]) -> u32 {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, &position)| binomial(position, i as u32 + 1))
+        .sum()
+}
+
+fn binomial(n: u32, k: u32) -> u32 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+// Picks the cards for the given ranks (a rank listed twice picks two cards of it), only from one suit if given
+fn pick_cards(cards: &[Card], ranks: &[usize], suit: Option<usize>) -> Vec<Card> {
+    let mut left: Vec<Card> = cards.to_vec();
+    let mut picked = Vec::new();
+    for &rank in ranks {
+        let position = left.iter().position(|card| {
+            let (card_rank, card_suit) = rank_and_suit(card);
+            card_rank == rank && suit.is_none_or(|suit| suit == card_suit)
+        });
+        if let Some(position) = position {
+            picked.push(left.remove(position));
+        }
+    }
+    picked
+}
@@ -0,0 +1,249 @@
+use super::card::Card;
+use super::deck::Deck;
+use super::error::FairnessError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub type Commitment = [u8; 32]; // SHA-256 hash of the server seed, published before the cards are dealt
+
+pub const MAX_ENTROPY_SIZE: usize = 64; // Most bytes of entropy taken from one client
+
+/// Commit–reveal shuffle, so the players don't have to trust the host's shuffle.
+///
+/// # Behavior
+/// - The host picks a secret server seed and publishes its commitment (the SHA-256 hash of the seed) before dealing.
+/// - Every client can add their own entropy after seeing the commitment. The host can't pick a seed that
+///   works well with the entropy since it already committed to it, and the clients can't see the seed.
+/// - The deck is shuffled from the hash of the server seed and all client entropy, see deck_seed().
+/// - When the hand is over the host reveals the seed and anyone can check the deal with verify().
+///
+/// # Example
+/// '''
+/// let mut fair = FairShuffle::new();
+/// let commitment = fair.commitment(); // Sent to every client before dealing
+/// fair.add_client_entropy(b"entropy from a client");
+///
+/// game.set_next_seed(fair.deck_seed());
+/// game.start_hand()?;
+///
+/// // After the hand, every client checks what it saw
+/// let reveal = fair.reveal();
+/// verify(&commitment, &reveal, &seen_cards(&dealt_seats, seat, &hole_cards, &board))?;
+/// assert!(reveal.has_entropy(b"entropy from a client"));
+/// '''
+#[derive(Clone, Debug)]
+pub struct FairShuffle {
+    server_seed: [u8; 32], // Secret until the hand is over
+    client_entropy: Vec<Vec<u8>>,
+}
+
+// Everything a client needs to check a deal after the hand is over
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reveal {
+    pub server_seed: [u8; 32],
+    pub client_entropy: Vec<Vec<u8>>,
+}
+
+impl Default for FairShuffle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FairShuffle {
+    // Create a shuffle with a random server seed
+    pub fn new() -> Self {
+        FairShuffle::from_server_seed(rand::random())
+    }
+
+    pub fn from_server_seed(server_seed: [u8; 32]) -> Self {
+        FairShuffle { server_seed, client_entropy: Vec::new() }
+    }
+
+    // The hash to publish before dealing
+    pub fn commitment(&self) -> Commitment {
+        commit(&self.server_seed)
+    }
+
+    // Entropy from a client, has to be added before deck_seed() is used to deal
+    pub fn add_client_entropy(&mut self, entropy: &[u8]) {
+        self.client_entropy.push(entropy.to_vec());
+    }
+
+    // The seed to shuffle the deck with, give it to Game::set_next_seed or Deck::shuffle_seeded
+    pub fn deck_seed(&self) -> u64 {
+        deck_seed(&self.server_seed, &self.client_entropy)
+    }
+
+    // Ends the hand and gives away the server seed so clients can verify the deal
+    pub fn reveal(self) -> Reveal {
+        Reveal { server_seed: self.server_seed, client_entropy: self.client_entropy }
+    }
+}
+
+impl Reveal {
+    // True if a client's entropy went into the shuffle. Without it the client has to trust that the host didn't pick the seed
+    pub fn has_entropy(&self, entropy: &[u8]) -> bool {
+        self.client_entropy.iter().any(|used| used == entropy)
+    }
+
+    pub fn deck_seed(&self) -> u64 {
+        deck_seed(&self.server_seed, &self.client_entropy)
+    }
+
+    // The deck in the order it was in right after shuffling, before any card was dealt
+    pub fn shuffled_deck(&self) -> Deck {
+        let mut deck = Deck::new();
+        deck.shuffle_seeded(self.deck_seed());
+        deck
+    }
+}
+
+// SHA-256 hash of a server seed
+pub fn commit(server_seed: &[u8; 32]) -> Commitment {
+    Sha256::digest(server_seed).into()
+}
+
+/// Checks that the cards a player saw came from the seed the host committed to.
+///
+/// # Parameters
+/// - 'commitment': The commitment that was published before dealing.
+/// - 'reveal': The server seed and client entropy revealed after the hand.
+/// - 'seen': Cards together with their deck position, 0 being the first card drawn. Any subset of the deal works,
+///   so every client can check its own hole cards and the board without knowing the other hands, see seen_cards().
+///
+/// # Returns
+/// - Ok(()) if the seed matches the commitment and every card is at its position in the deck shuffled from it.
+/// - FairnessError::CommitmentMismatch if the revealed seed isn't the one that was committed to.
+/// - FairnessError::DealMismatch if the seed is right but a card doesn't match, 'position' is the deck position of the card.
+pub fn verify(commitment: &Commitment, reveal: &Reveal, seen: &[(usize, Card)]) -> Result<(), FairnessError> {
+    if commit(&reveal.server_seed) != *commitment {
+        return Err(FairnessError::CommitmentMismatch);
+    }
+
+    // Cards are drawn from the end of the deck
+    let deck = reveal.shuffled_deck();
+    for &(position, card) in seen {
+        if deck.cards.iter().rev().nth(position) != Some(&card) {
+            return Err(FairnessError::DealMismatch { position });
+        }
+    }
+    Ok(())
+}
+
+/// The deck positions of the cards one player saw in a hand, to check them with verify().
+///
+/// # Parameters
+/// - 'dealt_seats': The seats that were dealt cards, in the order Game::start_hand dealt them.
+/// - 'seat': The player's seat.
+/// - 'hole_cards': The player's hole cards, left out if the seat wasn't dealt in.
+/// - 'board': The board as far as it was dealt.
+///
+/// # Behavior
+/// - Game::start_hand deals two cards to each seat in turn, and the board is drawn after every hole card in the order it's dealt.
+pub fn seen_cards(dealt_seats: &[usize], seat: usize, hole_cards: &[Card], board: &[Card]) -> Vec<(usize, Card)> {
+    let mut seen = Vec::new();
+    if let Some(order) = dealt_seats.iter().position(|&dealt| dealt == seat) {
+        seen.extend(hole_cards.iter().enumerate().map(|(i, &card)| (order * 2 + i, card)));
+    }
+    let board_start = dealt_seats.len() * 2;
+    seen.extend(board.iter().enumerate().map(|(i, &card)| (board_start + i, card)));
+    seen
+}
+
+// Hex string of a commitment, to show it to players or send it as text
+pub fn to_hex(commitment: &Commitment) -> String {
+    commitment.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Hashes the server seed and every client entropy (each prefixed by its length so they can't be shifted around)
+// and uses the first eight bytes as the deck seed
+fn deck_seed(server_seed: &[u8; 32], client_entropy: &[Vec<u8>]) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(server_seed);
+    for entropy in client_entropy {
+        hasher.update((entropy.len() as u64).to_le_bytes());
+        hasher.update(entropy);
+    }
+    let hash = hasher.finalize();
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use crate::structs::enums::{Action, Street};
+
+    // Plays a hand shuffled from 'fair' down to the river, player 1 sits out without chips
+    fn deal_river(fair: &FairShuffle) -> Game {
+        let mut game = Game::new(3, 1000);
+        game.players[1].chips.chips = 0;
+        game.set_next_seed(fair.deck_seed());
+        game.start_hand().unwrap();
+        while game.street != Street::River {
+            if game.betting_round_over() {
+                game.advance().unwrap();
+                continue;
+            }
+            let player = game.current_player;
+            let action = if game.legal_actions(player).can_check { Action::Check } else { Action::Call };
+            game.apply_action(player, action).unwrap();
+        }
+        game
+    }
+
+    fn seen_by(game: &Game, seat: usize) -> Vec<(usize, Card)> {
+        seen_cards(&[0, 2], seat, &game.players[seat].hand.cards, &game.board.cards)
+    }
+
+    #[test]
+    fn every_player_can_verify_an_honest_deal() {
+        let mut fair = FairShuffle::from_server_seed([7; 32]);
+        let commitment = fair.commitment();
+        fair.add_client_entropy(b"first client");
+        fair.add_client_entropy(b"second client");
+        let game = deal_river(&fair);
+        let reveal = fair.reveal();
+        assert!(reveal.has_entropy(b"first client") && reveal.has_entropy(b"second client"));
+        assert!(!reveal.has_entropy(b"third client"));
+
+        for seat in [0, 2] {
+            assert_eq!(verify(&commitment, &reveal, &seen_by(&game, seat)), Ok(()));
+        }
+        // The player sitting out only saw the board
+        assert_eq!(seen_by(&game, 1).len(), 5);
+        assert_eq!(verify(&commitment, &reveal, &seen_by(&game, 1)), Ok(()));
+    }
+
+    #[test]
+    fn tampered_seed_is_a_commitment_mismatch() {
+        let fair = FairShuffle::from_server_seed([7; 32]);
+        let commitment = fair.commitment();
+        let game = deal_river(&fair);
+        let mut reveal = fair.reveal();
+        reveal.server_seed[0] ^= 1;
+
+        assert_eq!(verify(&commitment, &reveal, &seen_by(&game, 0)), Err(FairnessError::CommitmentMismatch));
+    }
+
+    #[test]
+    fn tampered_card_is_a_deal_mismatch() {
+        let fair = FairShuffle::from_server_seed([7; 32]);
+        let commitment = fair.commitment();
+        let mut game = deal_river(&fair);
+        let reveal = fair.reveal();
+
+        // Swap the turn for the river, the turn is the fourth board card after four hole cards
+        game.board.cards.swap(3, 4);
+        assert_eq!(verify(&commitment, &reveal, &seen_by(&game, 2)), Err(FairnessError::DealMismatch { position: 7 }));
+
+        // Entropy added after the deal gives another deck
+        let mut late = FairShuffle::from_server_seed([7; 32]);
+        late.add_client_entropy(b"too late");
+        let game = deal_river(&FairShuffle::from_server_seed([7; 32]));
+        assert_eq!(verify(&commitment, &late.reveal(), &seen_by(&game, 0)), Err(FairnessError::DealMismatch { position: 0 }));
+    }
+}
@@ -1,16 +1,40 @@
-use super::card::Card;
+use super::card::{parse_cards, Card};
 use super::collection::Collection;
-use super::misc::rank_to_words;
-use poker_eval::eval::five::{build_tables as build_tables_five, get_rank_five, TableFive};
-use poker_eval::eval::seven::{build_tables as build_tables_seven, get_rank as get_rank_seven, TableSeven};
-use std::sync::Arc;
-use itertools::Itertools;
+use super::enums::{HandCategory, Rank};
+use super::error::ParseCardError;
+use super::evaluator::Evaluator;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Hand {
     pub cards: Vec<Card>,
 }
 
+impl Default for Hand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The result of evaluating a hand together with the board
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandStrength {
+    pub category: HandCategory,
+    pub rank: u32, // 0-7461 where a higher number => better hand
+    pub cards: Vec<Card>, // The best five cards, the cards making the combination first and kickers after
+    pub kickers: Vec<Card>, // The cards in 'cards' that aren't part of the combination, highest first
+}
+
+impl HandStrength {
+    // The hand type in words, e.g. "One Pair"
+    pub fn description(&self) -> &'static str {
+        self.category.to_words()
+    }
+}
+
 impl Hand {
     pub fn new() -> Self {
         Hand { cards: Vec::new() }
@@ -20,87 +44,205 @@ impl Hand {
     /// 
     /// # Parameters
     /// - 'board': The cards on the board (flop, turn, river).
-    /// - 't5': precalculated rank lookup table for five cards.
-    /// - 't7': precalculated rank lookup table for seven cards.
     /// 
     /// # Returns
-    /// A tuple (rank, description) where:
-    /// - 'rank': u32 from 0-7452 where lower number => better hand
-    /// - 'description': &str that contains the rank converted to the actual hand type, e.g. "high card", "one pair",...
-    /// 
-    /// # Behavior
-    /// - Returns (0, "error") if amount of cards is not a real poker hand.
-    /// - For five cards (hand + flop), uses t5 to check hand rank.
-    /// - For six cards (hand + flop + turn), creates all possible 5 combinations, evaluates them by t5, and returns the best rank.
-    /// - For seven cards (hand + flop + turn + river), uses t7 to check hand rank.
+    /// An Option<HandStrength> where:
+    /// - HandStrength: contains the hand category, the rank (higher => better), the best five cards and the kickers.
+    /// - None: if the amount of cards in hand + board is not 5, 6 or 7.
     /// 
     /// # Example
     /// '''
-    /// let hand_rank: (u32, &str) = game.players[0].hand.evaluate(&game.board, &game.t5, &game.t7);
-    /// println!("Hand rank: {}, Hand type: {}", hand_rank.0, hand_rank.1);
+    /// if let Some(strength) = game.players[0].hand.evaluate(&game.board.cards) {
+    ///     println!("Hand rank: {}, Hand type: {}", strength.rank, strength.description());
+    /// }
     /// '''
-    pub fn evaluate(&self, board: &Vec<Card>, t5: &TableFive, t7: &Arc<TableSeven>) -> (u32, &str) {
+    pub fn evaluate(&self, board: &[Card]) -> Option<HandStrength> {
         let mut cards = Collection::new();
         cards.cards.extend(&self.cards);
         cards.cards.extend(board);
-        let amount_of_cards: usize = &self.cards.len() + board.len();
 
-        if amount_of_cards < 5 || amount_of_cards > 7 {
-            return (0, "Wrong amount of cards in hand + board in evaluate()");
-        }
+        let (rank, best_five) = Evaluator::shared().evaluate(&cards.cards)?;
+        let category = HandCategory::from_rank(rank)?;
+        let (best_five, kickers) = order_best_five(best_five, category);
+        Some(HandStrength { category, rank, cards: best_five, kickers })
+    }
+}
 
-        if amount_of_cards == 5 {
-            let card_vector: Vec<usize> = cards.cards
-                .into_iter()
-                .map(|card| card.as_index())
-                .collect::<Vec<usize>>();
-
-                match card_vector.try_into() {
-                    Ok(card_values) => {
-                        let hand_rank: u32 = get_rank_five(&t5, card_values);
-                        return (hand_rank, rank_to_words(hand_rank))
-                    },
-                    Err(_) => return (0, "Failed to convert to 5-card array in evaluate()"),
-                }
+// The cards next to each other, e.g. "AhKh"
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for card in &self.cards {
+            write!(f, "{}", card)?;
         }
-        else if amount_of_cards == 6 {
-            let hand_rank: Option<u32> = cards.cards
-                .into_iter()
-                .map(|card| card.as_index())
-                .collect::<Vec<usize>>()
-                .into_iter()
-                .combinations(5)
-                .map(|combination| {
-                    match combination.try_into() {
-                        Ok(array) => Some(get_rank_five(&t5, array)),
-                        Err(_) => None,
-                    }
-                })
-                .flatten()
-                .max();
-
-            match hand_rank {
-                Some(rank) => (rank, rank_to_words(rank)),
-                None => (0, "Failed to evaluate 6-card hand in evaluate()")
-            };
+        Ok(())
+    }
+}
+
+// A hand is exactly two cards, "AhKh" or "Ah Kh"
+impl FromStr for Hand {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards = parse_cards(s)?;
+        if cards.len() != 2 {
+            return Err(ParseCardError::WrongCardCount { expected: "2".to_string(), found: cards.len() });
         }
-        else if amount_of_cards == 7 {
-            let card_vector: Vec<usize> = cards.cards
-                .into_iter()
-                .map(|card| card.as_index())
-                .collect();
-        
-            match card_vector.try_into() {
-                Ok(card_values) => {
-                    let hand_rank: u32 = get_rank_seven(&t7, card_values);
-                    return (hand_rank, rank_to_words(hand_rank))
-                },
-                Err(_) => return (0, "Failed to convert to 7-card array in evaluate()"),
+        Ok(Hand { cards })
+    }
+}
+
+// Sorts the best five cards with the cards making the combination first (biggest group, then highest rank)
+// and splits out the kickers. A wheel straight (A-2-3-4-5) puts the ace last since it counts as low.
+fn order_best_five(five: Vec<Card>, category: HandCategory) -> (Vec<Card>, Vec<Card>) {
+    let count_of = |card: &Card| five.iter().filter(|other| other.rank == card.rank).count();
+
+    let mut ordered = five.clone();
+    ordered.sort_by_key(|card| Reverse((count_of(card), card.rank)));
+
+    let is_straight = matches!(category, HandCategory::Straight | HandCategory::StraightFlush);
+    let is_wheel = is_straight && ordered[0].rank == Rank::Ace && ordered[1].rank == Rank::Five;
+    if is_wheel {
+        ordered.rotate_left(1);
+    }
+
+    let kickers: Vec<Card> = match category {
+        HandCategory::HighCard => ordered[1..].to_vec(),
+        HandCategory::OnePair | HandCategory::TwoPair | HandCategory::ThreeOfAKind | HandCategory::FourOfAKind => ordered
+            .iter()
+            .filter(|card| count_of(card) == 1)
+            .copied()
+            .collect(),
+        _ => Vec::new(),
+    };
+    (ordered, kickers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::board::Board;
+
+    // One hand per category. The hand and the first three board cards make the category, the last two don't improve it
+    const HANDS: [(&str, &str, HandCategory); 9] = [
+        ("AhJd", "9c6s3h2d8c", HandCategory::HighCard),
+        ("AhAs", "9c6s3h2dJc", HandCategory::OnePair),
+        ("AhAs", "9c9s3hKd2c", HandCategory::TwoPair),
+        ("AhAs", "Ac9s3h2dJc", HandCategory::ThreeOfAKind),
+        ("9h8s", "7c6d5h2sKc", HandCategory::Straight),
+        ("AhJh", "9h6h3h2cKd", HandCategory::Flush),
+        ("AhAs", "Ac9s9hKd2c", HandCategory::FullHouse),
+        ("AhAs", "AcAd9hKd2c", HandCategory::FourOfAKind),
+        ("9h8h", "7h6h5h2sKc", HandCategory::StraightFlush),
+    ];
+
+    fn cards(s: &str) -> Vec<Card> {
+        parse_cards(s).unwrap()
+    }
+
+    // Evaluates a hand with the first 'board_cards' cards of a board
+    fn strength(hand: &str, board: &str, board_cards: usize) -> HandStrength {
+        let hand: Hand = hand.parse().unwrap();
+        let board: Board = board.parse().unwrap();
+        hand.evaluate(&board.cards[..board_cards]).unwrap()
+    }
+
+    #[test]
+    fn every_category_with_five_six_and_seven_cards() {
+        for (hand, board, category) in HANDS {
+            for board_cards in 3..=5 {
+                let strength = strength(hand, board, board_cards);
+                assert_eq!(strength.category, category, "{} {} with {} board cards", hand, board, board_cards);
+                assert_eq!(HandCategory::from_rank(strength.rank), Some(category));
+                assert_eq!(strength.cards.len(), 5);
             }
         }
-        else {
-            println!("How did we get here...?"); // If you know, you know
+    }
+
+    #[test]
+    fn categories_rank_in_order() {
+        for board_cards in 3..=5 {
+            let ranks: Vec<u32> = HANDS.iter().map(|(hand, board, _)| strength(hand, board, board_cards).rank).collect();
+            assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", ranks);
+        }
+    }
+
+    #[test]
+    fn six_cards_are_evaluated_on_the_turn() {
+        // Six card hands used to fall through to rank 0 and "ERROR"
+        let hand: Hand = "AhAs".parse().unwrap();
+        let strength = hand.evaluate(&cards("9c6s3h2d")).unwrap();
+        assert_eq!(strength.category, HandCategory::OnePair);
+        assert_eq!(strength.description(), "One Pair");
+        assert!(strength.rank >= 1277);
+        assert_eq!(strength.cards, cards("AhAs9c6s3h"));
+
+        // The turn card can make a better hand than the flop
+        let turned: Hand = "9h8s".parse().unwrap();
+        assert_eq!(turned.evaluate(&cards("7c6d2s")).unwrap().category, HandCategory::HighCard);
+        assert_eq!(turned.evaluate(&cards("7c6d2s5h")).unwrap().category, HandCategory::Straight);
+    }
+
+    #[test]
+    fn wrong_amount_of_cards_is_none() {
+        let hand: Hand = "AhAs".parse().unwrap();
+        assert_eq!(hand.evaluate(&cards("9c6s")), None);
+        assert_eq!(hand.evaluate(&cards("9c6s3h2dJcKd")), None);
+    }
+
+    #[test]
+    fn wheel_is_the_lowest_straight_with_the_ace_last() {
+        let wheel = strength("Ah2c", "3d4s5h9cKd", 5);
+        assert_eq!(wheel.category, HandCategory::Straight);
+        assert_eq!(wheel.cards, cards("5h4s3d2cAh"));
+        assert!(wheel.kickers.is_empty());
+
+        let six_high = strength("6h2c", "3d4s5h9cKd", 5);
+        assert!(wheel.rank < six_high.rank);
+        assert_eq!(six_high.cards, cards("6h5h4s3d2c"));
+
+        let steel_wheel = strength("Ah2h", "3h4h5h9cKd", 5);
+        assert_eq!(steel_wheel.category, HandCategory::StraightFlush);
+        assert_eq!(steel_wheel.cards, cards("5h4h3h2hAh"));
+    }
+
+    #[test]
+    fn kickers_are_the_cards_outside_the_combination() {
+        let high_card = strength("AhJd", "9c6s3h2d8c", 5);
+        assert_eq!(high_card.cards, cards("AhJd9c8c6s"));
+        assert_eq!(high_card.kickers, cards("Jd9c8c6s"));
+
+        let pair_on_flop = strength("AhAs", "9c6s3h2dJc", 3);
+        assert_eq!(pair_on_flop.kickers, cards("9c6s3h"));
+        let pair_on_river = strength("AhAs", "9c6s3h2dJc", 5);
+        assert_eq!(pair_on_river.cards, cards("AhAsJc9c6s"));
+        assert_eq!(pair_on_river.kickers, cards("Jc9c6s"));
+
+        assert_eq!(strength("AhAs", "9c9s3hKd2c", 5).kickers, cards("Kd"));
+        assert_eq!(strength("AhAs", "Ac9s3h2dJc", 5).kickers, cards("Jc9s"));
+        assert_eq!(strength("AhAs", "AcAd9hKd2c", 5).kickers, cards("Kd"));
+        assert!(strength("AhAs", "Ac9s9hKd2c", 5).kickers.is_empty());
+        assert!(strength("AhJh", "9h6h3h2cKd", 5).kickers.is_empty());
+    }
+
+    #[test]
+    fn hand_round_trips() {
+        for text in ["AhKh", "2c2d", "Ts9s", "AsAc"] {
+            let hand: Hand = text.parse().unwrap();
+            assert_eq!(hand.cards.len(), 2);
+            assert_eq!(hand.to_string(), text);
         }
-        (0, "ERROR")
+        assert_eq!("ah kh".parse::<Hand>().unwrap().to_string(), "AhKh");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn hand_parse_errors() {
+        let wrong_count = |found| Err(ParseCardError::WrongCardCount { expected: "2".to_string(), found });
+        assert_eq!("Ah".parse::<Hand>(), wrong_count(1));
+        assert_eq!("AhKhQh".parse::<Hand>(), wrong_count(3));
+        assert_eq!("".parse::<Hand>(), wrong_count(0));
+        assert_eq!("AhAh".parse::<Hand>(), Err(ParseCardError::DuplicateCard("Ah".to_string())));
+        assert_eq!("AhK".parse::<Hand>(), Err(ParseCardError::MissingSuit('K')));
+        assert_eq!("AhKq".parse::<Hand>(), Err(ParseCardError::InvalidSuit('q')));
+        assert_eq!("Ah0h".parse::<Hand>(), Err(ParseCardError::InvalidRank('0')));
+    }
+}
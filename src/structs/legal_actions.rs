@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+// The actions a player is allowed to take right now, together with the amounts that go with them.
+// Bet and raise amounts are totals for the round, the same as Action::Bet and Action::RaiseTo.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalActions {
+    pub can_fold: bool,
+    pub can_check: bool,
+    pub can_call: bool,
+    pub call_amount: u32, // Chips the player has to put in to call
+    pub can_bet: bool, // Opening bet, nobody has bet this round
+    pub can_raise: bool,
+    pub min_raise_to: u32, // Smallest allowed bet or raise. Lower than the minimum raise if the player can only go all in for less
+    pub max_raise_to: u32, // Largest allowed bet or raise, which puts the player all in
+    pub can_all_in: bool,
+}
@@ -6,4 +6,14 @@ pub mod player;
 pub mod hand;
 pub mod collection;
 pub mod pot;
-pub mod playerchips;
\ No newline at end of file
+pub mod playerchips;
+pub mod error;
+pub mod legal_actions;
+pub mod evaluator;
+pub mod fairness;
+pub mod board;
+pub mod card_set;
+pub mod equity;
+pub mod range;
+pub mod protocol;
+pub mod view;
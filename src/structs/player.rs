@@ -1,11 +1,13 @@
 use super::hand::Hand;
 use super::playerchips::PlayerChips;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Player {
     pub hand: Hand,
     pub chips: PlayerChips,
     pub is_folded: bool,
+    pub is_all_in: bool, // All in players have no chips left to bet with and are skipped until showdown
 }
 impl Player {
     pub fn new(initial_chips: u32) -> Self {
@@ -13,6 +15,7 @@ impl Player {
             hand: Hand::new(),
             chips: PlayerChips::new(initial_chips),
             is_folded: false,
+            is_all_in: false,
         }
     }
 }
\ No newline at end of file
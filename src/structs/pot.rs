@@ -1,11 +1,20 @@
+use serde::{Deserialize, Serialize};
 use std::vec;
 
-#[derive(Debug)]
+// A main pot or side pot, together with the players that are allowed to win it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SidePot {
+    pub amount: u32,
+    pub eligible_players: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Pot {
     pub total: u32,
     pub contributions: Vec<u32>,
     pub current_bet: u32,
     pub player_bets: Vec<u32>,
+    pub last_raise: u32, // Size of the last full bet or raise this round, the minimum raise
 }
 
 impl Pot {
@@ -16,6 +25,7 @@ impl Pot {
             contributions: vec![0; players], // No one has any contribution in the start
             current_bet: 0,
             player_bets: vec![0; players],
+            last_raise: 0,
         }
     }
     // Add players contribution to the pot
@@ -31,15 +41,79 @@ impl Pot {
         }
     }
 
+    // Add an ante to the pot. Antes are dead money, so they don't count towards the player's bet this round
+    pub fn add_ante(&mut self, player_index: usize, amount: u32) {
+        if player_index < self.contributions.len() {
+            self.contributions[player_index] += amount;
+            self.total += amount;
+        }
+    }
+
     // Copy the amount of chips that were contributed to then be able to add contribution to the pot
     pub fn get_player_contribution(&self, player_index: usize) -> u32 {
         self.contributions.get(player_index).copied().unwrap_or(0)
     }
 
+    /// Splits the pot into a main pot and side pots from what every player has contributed.
+    ///
+    /// # Parameters
+    /// - 'folded': one entry per player, true if that player has folded. Folded players' chips stay in the pots but they can't win any of them.
+    ///
+    /// # Returns
+    /// - Vec<SidePot> where the first element is the main pot and the rest are the side pots in the order they were created.
+    ///
+    /// # Behavior
+    /// - Every distinct contribution among the non-folded players closes a pot, so an all-in player can only win the chips they covered.
+    /// - Chips that folded players contributed above the highest non-folded contribution are added to the last pot.
+    /// - A bet that nobody called ends up in a pot where the bettor is the only eligible player, so it goes back to them.
+    pub fn side_pots(&self, folded: &[bool]) -> Vec<SidePot> {
+        let is_folded = |player_index: usize| folded.get(player_index).copied().unwrap_or(false);
+
+        let mut levels: Vec<u32> = self.contributions
+            .iter()
+            .enumerate()
+            .filter(|&(i, &amount)| amount > 0 && !is_folded(i))
+            .map(|(_, &amount)| amount)
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+
+        let mut pots: Vec<SidePot> = Vec::new();
+        let mut previous_level = 0;
+        for level in levels {
+            let amount: u32 = self.contributions
+                .iter()
+                .map(|&contribution| contribution.min(level) - contribution.min(previous_level))
+                .sum();
+            let eligible_players: Vec<usize> = (0..self.contributions.len())
+                .filter(|&i| !is_folded(i) && self.contributions[i] >= level)
+                .collect();
+            pots.push(SidePot { amount, eligible_players });
+            previous_level = level;
+        }
+
+        // Whatever is left was put in by players that folded after betting more than anyone still in the hand
+        let leftover: u32 = self.contributions
+            .iter()
+            .map(|&contribution| contribution.saturating_sub(previous_level))
+            .sum();
+        if leftover > 0 {
+            match pots.last_mut() {
+                Some(last_pot) => last_pot.amount += leftover,
+                None => pots.push(SidePot {
+                    amount: leftover,
+                    eligible_players: (0..self.contributions.len()).filter(|&i| !is_folded(i)).collect(),
+                }),
+            }
+        }
+        pots
+    }
+
     // Reset bets so every possible playeraction is available in the start of the next round
     pub fn reset_round(&mut self) {
         self.current_bet = 0;
         self.player_bets = vec![0; self.player_bets.len()];
+        self.last_raise = 0;
     }
 
     // Reset pot after a round
@@ -48,4 +122,74 @@ impl Pot {
         self.contributions = vec![0; self.contributions.len()];
         self.reset_round();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A pot where every player has put in the given amounts
+    fn pot_with(contributions: &[u32]) -> Pot {
+        let mut pot = Pot::new(contributions.len());
+        for (i, &amount) in contributions.iter().enumerate() {
+            pot.add_constribution(i, amount);
+        }
+        pot
+    }
+
+    #[test]
+    fn single_pot_when_everyone_puts_in_the_same() {
+        let pot = pot_with(&[100, 100, 100]);
+        assert_eq!(pot.side_pots(&[false; 3]), vec![SidePot { amount: 300, eligible_players: vec![0, 1, 2] }]);
+    }
+
+    #[test]
+    fn three_way_all_in_with_three_stack_sizes() {
+        let pot = pot_with(&[100, 300, 500]);
+        let pots = pot.side_pots(&[false; 3]);
+        assert_eq!(
+            pots,
+            vec![
+                SidePot { amount: 300, eligible_players: vec![0, 1, 2] },
+                SidePot { amount: 400, eligible_players: vec![1, 2] },
+                SidePot { amount: 200, eligible_players: vec![2] }, // Uncalled, goes back to player 2
+            ]
+        );
+        assert_eq!(pots.iter().map(|pot| pot.amount).sum::<u32>(), pot.total);
+    }
+
+    #[test]
+    fn uncalled_bet_goes_back_to_the_bettor() {
+        let pot = pot_with(&[50, 200]);
+        assert_eq!(
+            pot.side_pots(&[false; 2]),
+            vec![
+                SidePot { amount: 100, eligible_players: vec![0, 1] },
+                SidePot { amount: 150, eligible_players: vec![1] },
+            ]
+        );
+    }
+
+    #[test]
+    fn folded_players_chips_stay_in_the_pot() {
+        let pot = pot_with(&[50, 100, 100]);
+        assert_eq!(pot.side_pots(&[true, false, false]), vec![SidePot { amount: 250, eligible_players: vec![1, 2] }]);
+    }
+
+    #[test]
+    fn folded_chips_above_everyone_else_go_to_the_last_pot() {
+        let pot = pot_with(&[200, 100, 150]);
+        assert_eq!(
+            pot.side_pots(&[true, false, false]),
+            vec![
+                SidePot { amount: 300, eligible_players: vec![1, 2] },
+                SidePot { amount: 150, eligible_players: vec![2] },
+            ]
+        );
+    }
+
+    #[test]
+    fn everyone_folded_but_one() {
+        let pot = pot_with(&[10, 20, 0]);
+        assert_eq!(pot.side_pots(&[true, false, true]), vec![SidePot { amount: 30, eligible_players: vec![1] }]);
+    }
+}
@@ -0,0 +1,254 @@
+use super::enums::Action;
+use super::error::ProtocolError;
+use super::fairness::{Commitment, Reveal};
+use super::card::Card;
+use super::view::{PlayerView, ShowdownHand};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+
+// Bumped whenever a message changes, clients and servers with different versions refuse to talk to each other
+pub const PROTOCOL_VERSION: u32 = 6;
+
+// Largest frame accepted, protects against garbage lengths allocating huge buffers
+pub const MAX_FRAME_SIZE: usize = 1 << 20;
+
+/// Messages from a client to the server.
+///
+/// # Behavior
+/// - The first message on a connection must be Hello with the client's PROTOCOL_VERSION.
+///   A client that lost its connection sends the token from its Welcome to get its seat back, a new client sends None.
+/// - Join takes a seat at the table, the game starts when every seat has joined.
+/// - Action amounts are totals for the round, the same as in Game::apply_action.
+/// - Entropy answers Commit with random bytes of the client's own, at most fairness::MAX_ENTROPY_SIZE of them.
+///   The next hand is shuffled from the committed seed and the entropy of every player.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Hello { version: u32, token: Option<String> },
+    Join { name: String },
+    Action(Action),
+    Entropy { entropy: Vec<u8> },
+    Chat { message: String },
+    Ping { id: u64 },
+    Pong { id: u64 },
+}
+
+/// Messages from the server to a client.
+///
+/// # Behavior
+/// - Welcome answers Hello and tells the client its seat and session token. A client with the wrong version,
+///   an unknown token or no free seat gets an Error and is disconnected.
+/// - A reconnecting client gets the current View right after Welcome.
+/// - View is sent to every player after every change, made with Game::view_for so it only has the cards they may see.
+///   At a showdown it has the hands of everyone that didn't fold and who won.
+/// - Deal gives every player their hole cards when a hand starts, and Showdown is sent to everyone when it's over.
+///   They are the same cards as in View, for clients that only want to follow the hands.
+/// - Commit is sent before every hand. The hand is dealt once every connected player has answered with Entropy,
+///   or after a few seconds without the players that didn't answer. Reveal is sent when the hand is over so the players
+///   can check the deal with fairness::verify and that their entropy was used. A reconnecting client gets the Commit again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome { version: u32, seat: usize, token: String }, // The token gets the seat back after a lost connection
+    Seat { seat: usize, name: String }, // A player joined the table
+    Left { seat: usize }, // A player disconnected, their seat is held for them
+    Reconnected { seat: usize }, // A player that disconnected is back
+    View(Box<PlayerView>),
+    Deal { cards: Vec<Card> }, // The receiving player's hole cards
+    Showdown { hands: Vec<ShowdownHand>, winners: Vec<usize> }, // No hands if everyone else folded
+    Acted { seat: usize, action: Action },
+    Chat { seat: usize, message: String },
+    Error { message: String },
+    Ping { id: u64 },
+    Pong { id: u64 },
+    GameOver { winner: Option<usize> },
+    Commit { commitment: Commitment }, // The deck for the next hand is shuffled from the seed behind this hash
+    Reveal { reveal: Reveal, dealt_seats: Vec<usize> }, // The seed behind the last Commit and the seats that were dealt cards, in order
+}
+
+/// Writes one message as a frame: the length of the body as a big endian u32, then the body as JSON.
+///
+/// # Example
+/// '''
+/// let mut stream = TcpStream::connect("127.0.0.1:7878")?;
+/// write_message(&mut stream, &ClientMessage::Hello { version: PROTOCOL_VERSION, token: None })?;
+/// let reply: ServerMessage = read_message(&mut stream)?;
+/// '''
+pub fn write_message<W: Write, M: Serialize>(writer: &mut W, message: &M) -> Result<(), ProtocolError> {
+    let frame = encode(message)?;
+    writer.write_all(&frame)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Reads one frame written by write_message. Blocks until the whole frame has arrived
+pub fn read_message<R: Read, M: DeserializeOwned>(reader: &mut R) -> Result<M, ProtocolError> {
+    let mut length = [0u8; 4];
+    if let Err(error) = reader.read_exact(&mut length) {
+        return Err(match error.kind() {
+            ErrorKind::UnexpectedEof => ProtocolError::ConnectionClosed,
+            _ => ProtocolError::Io(error),
+        });
+    }
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_SIZE {
+        return Err(ProtocolError::FrameTooLarge(length));
+    }
+
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+// A message as a complete frame, length prefix included
+pub fn encode<M: Serialize>(message: &M) -> Result<Vec<u8>, ProtocolError> {
+    let body = serde_json::to_vec(message)?;
+    if body.len() > MAX_FRAME_SIZE {
+        return Err(ProtocolError::FrameTooLarge(body.len()));
+    }
+    let mut frame = (body.len() as u32).to_be_bytes().to_vec();
+    frame.extend(body);
+    Ok(frame)
+}
+
+// Checks the version from a Hello or Welcome against ours
+pub fn check_version(version: u32) -> Result<(), ProtocolError> {
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::VersionMismatch { ours: PROTOCOL_VERSION, theirs: version });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+    use crate::structs::card::parse_cards;
+    use crate::structs::fairness::FairShuffle;
+    use crate::structs::hand::Hand;
+    use std::io::Cursor;
+
+    fn client_messages() -> Vec<ClientMessage> {
+        let mut messages = vec![
+            ClientMessage::Hello { version: PROTOCOL_VERSION, token: None },
+            ClientMessage::Hello { version: PROTOCOL_VERSION, token: Some("0123456789abcdef".to_string()) },
+            ClientMessage::Join { name: "Joel".to_string() },
+            ClientMessage::Chat { message: "gl hf".to_string() },
+            ClientMessage::Ping { id: 1 },
+            ClientMessage::Pong { id: u64::MAX },
+            ClientMessage::Entropy { entropy: vec![0, 7, 255] },
+        ];
+        for action in [Action::Fold, Action::Check, Action::Call, Action::Bet(20), Action::RaiseTo(60), Action::AllIn] {
+            messages.push(ClientMessage::Action(action));
+        }
+        messages
+    }
+
+    fn server_messages() -> Vec<ServerMessage> {
+        let mut game = Game::new(3, 1000);
+        game.start_hand().unwrap();
+        let hand: Hand = "AhAs".parse().unwrap();
+        let strength = hand.evaluate(&parse_cards("Kd7c3s").unwrap()).unwrap();
+        let shuffle = FairShuffle::from_server_seed([3; 32]);
+
+        vec![
+            ServerMessage::Welcome { version: PROTOCOL_VERSION, seat: 1, token: "0123456789abcdef".to_string() },
+            ServerMessage::Seat { seat: 2, name: "Gustav".to_string() },
+            ServerMessage::Left { seat: 2 },
+            ServerMessage::Reconnected { seat: 2 },
+            ServerMessage::View(Box::new(game.view_for(1))),
+            ServerMessage::Deal { cards: hand.cards.clone() },
+            ServerMessage::Showdown { hands: vec![ShowdownHand { seat: 0, hand, strength }], winners: vec![0] },
+            ServerMessage::Showdown { hands: Vec::new(), winners: vec![1] },
+            ServerMessage::Acted { seat: 0, action: Action::RaiseTo(60) },
+            ServerMessage::Chat { seat: 0, message: "nh".to_string() },
+            ServerMessage::Error { message: "It's not your turn".to_string() },
+            ServerMessage::Ping { id: 7 },
+            ServerMessage::Pong { id: 7 },
+            ServerMessage::GameOver { winner: Some(2) },
+            ServerMessage::GameOver { winner: None },
+            ServerMessage::Commit { commitment: shuffle.commitment() },
+            ServerMessage::Reveal { reveal: shuffle.reveal(), dealt_seats: vec![0, 2] },
+        ]
+    }
+
+    // Writes every message to one buffer and reads them back in order
+    fn round_trip<M: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug>(messages: &[M]) {
+        let mut buffer = Vec::new();
+        for message in messages {
+            write_message(&mut buffer, message).unwrap();
+        }
+        let encoded: Vec<u8> = messages.iter().flat_map(|message| encode(message).unwrap()).collect();
+        assert_eq!(buffer, encoded);
+
+        let mut reader = Cursor::new(buffer);
+        for message in messages {
+            assert_eq!(&read_message::<_, M>(&mut reader).unwrap(), message);
+        }
+        assert!(matches!(read_message::<_, M>(&mut reader), Err(ProtocolError::ConnectionClosed)));
+    }
+
+    #[test]
+    fn every_client_message_round_trips() {
+        round_trip(&client_messages());
+    }
+
+    #[test]
+    fn every_server_message_round_trips() {
+        round_trip(&server_messages());
+    }
+
+    #[test]
+    fn frame_is_length_then_json() {
+        let frame = encode(&ClientMessage::Ping { id: 1 }).unwrap();
+        let body = br#"{"Ping":{"id":1}}"#;
+        assert_eq!(frame[..4], (body.len() as u32).to_be_bytes());
+        assert_eq!(&frame[4..], body);
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let length = (MAX_FRAME_SIZE + 1) as u32;
+        let mut reader = Cursor::new(length.to_be_bytes().to_vec());
+        assert!(matches!(read_message::<_, ClientMessage>(&mut reader), Err(ProtocolError::FrameTooLarge(size)) if size == MAX_FRAME_SIZE + 1));
+
+        let mut reader = Cursor::new(u32::MAX.to_be_bytes().to_vec());
+        assert!(matches!(read_message::<_, ClientMessage>(&mut reader), Err(ProtocolError::FrameTooLarge(_))));
+
+        let huge = ClientMessage::Chat { message: "a".repeat(MAX_FRAME_SIZE) };
+        assert!(matches!(encode(&huge), Err(ProtocolError::FrameTooLarge(_))));
+        assert!(matches!(write_message(&mut Vec::new(), &huge), Err(ProtocolError::FrameTooLarge(_))));
+    }
+
+    #[test]
+    fn end_of_stream_is_connection_closed() {
+        let mut empty = Cursor::new(Vec::new());
+        assert!(matches!(read_message::<_, ServerMessage>(&mut empty), Err(ProtocolError::ConnectionClosed)));
+
+        // Closed halfway through the length
+        let mut cut = Cursor::new(vec![0, 0]);
+        assert!(matches!(read_message::<_, ServerMessage>(&mut cut), Err(ProtocolError::ConnectionClosed)));
+    }
+
+    #[test]
+    fn garbage_is_an_invalid_message() {
+        let body = b"not json";
+        let mut frame = (body.len() as u32).to_be_bytes().to_vec();
+        frame.extend(body);
+        assert!(matches!(read_message::<_, ClientMessage>(&mut Cursor::new(frame)), Err(ProtocolError::InvalidMessage(_))));
+
+        // A server message is not a client message
+        let frame = encode(&ServerMessage::GameOver { winner: None }).unwrap();
+        assert!(matches!(read_message::<_, ClientMessage>(&mut Cursor::new(frame)), Err(ProtocolError::InvalidMessage(_))));
+    }
+
+    #[test]
+    fn versions_have_to_match() {
+        assert!(check_version(PROTOCOL_VERSION).is_ok());
+        let theirs = PROTOCOL_VERSION + 1;
+        assert!(matches!(
+            check_version(theirs),
+            Err(ProtocolError::VersionMismatch { ours, theirs: version }) if ours == PROTOCOL_VERSION && version == theirs
+        ));
+        assert!(matches!(check_version(0), Err(ProtocolError::VersionMismatch { theirs: 0, .. })));
+    }
+}
@@ -0,0 +1,310 @@
+use super::card::{parse_cards, Card};
+use super::card_set::CardSet;
+use super::enums::{Rank, Suit};
+use super::error::ParseRangeError;
+use super::hand::Hand;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+// One specific pair of hole cards in a range, 'weight' is how often the hand is played this way (1.0 = always)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Combo {
+    pub cards: [Card; 2],
+    pub weight: f64,
+}
+
+impl Combo {
+    pub fn card_set(&self) -> CardSet {
+        CardSet::from(&self.cards[..])
+    }
+}
+
+/// The hands a player can have, written in standard range notation such as "QQ+, AKs, A5s-A2s".
+///
+/// # Notation
+/// - Pairs: "QQ", "QQ+" (queens or better), "99-66".
+/// - Suited and offsuit hands: "AKs", "AKo", "AK" (both), "ATs+" (AT to AK), "A5s-A2s".
+/// - Connectors and gappers with the same gap: "T9s-65s" (T9s, 98s, 87s, 76s and 65s), "KTo-85o".
+/// - Specific combos: "AhKh".
+/// - A weight can be added after a colon, "AKo:0.5" plays half of the offsuit AK combos.
+/// - Parts are separated by commas. If a combo is listed more than once the last weight is used.
+///
+/// # Example
+/// '''
+/// let range: Range = "QQ+, AKs, A5s-A2s".parse()?;
+/// println!("{} combos", range.len()); // 18 pairs + 4 AKs + 16 suited aces = 38
+/// '''
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Range {
+    pub combos: Vec<Combo>,
+}
+
+impl Range {
+    pub fn new() -> Self {
+        Range { combos: Vec::new() }
+    }
+
+    // Adds a combo, or changes its weight if it's already in the range
+    pub fn add(&mut self, cards: [Card; 2], weight: f64) {
+        let set = CardSet::from(&cards[..]);
+        match self.combos.iter_mut().find(|combo| combo.card_set() == set) {
+            Some(combo) => combo.weight = weight,
+            None => self.combos.push(Combo { cards, weight }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+
+    // Number of combos counted by their weights, e.g. "AKo:0.5" is 6 combos
+    pub fn weighted_len(&self) -> f64 {
+        self.combos.iter().map(|combo| combo.weight).sum()
+    }
+
+    // The range without the combos that use one of the given cards (card removal), e.g. cards on the board
+    pub fn without(&self, dead: CardSet) -> Range {
+        let combos = self.combos.iter().filter(|combo| combo.card_set().is_disjoint(dead)).copied().collect();
+        Range { combos }
+    }
+}
+
+// A range with a single hand in it
+impl From<&Hand> for Range {
+    fn from(hand: &Hand) -> Self {
+        let mut range = Range::new();
+        if let [first, second] = hand.cards[..] {
+            range.add([first, second], 1.0);
+        }
+        range
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut range = Range::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (notation, weight) = match part.split_once(':') {
+                Some((notation, weight)) => (notation.trim(), parse_weight(weight.trim())?),
+                None => (part, 1.0),
+            };
+            for cards in parse_part(notation)? {
+                range.add(cards, weight);
+            }
+        }
+        Ok(range)
+    }
+}
+
+// The kind of starting hand a part of the notation describes, the first rank is the highest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shape {
+    Pair(Rank),
+    Suited(Rank, Rank),
+    Offsuit(Rank, Rank),
+    Any(Rank, Rank), // Both suited and offsuit
+}
+
+fn parse_weight(weight: &str) -> Result<f64, ParseRangeError> {
+    match weight.parse::<f64>() {
+        Ok(value) if value > 0.0 && value <= 1.0 => Ok(value),
+        _ => Err(ParseRangeError::InvalidWeight(weight.to_string())),
+    }
+}
+
+// Every combo in one comma separated part, e.g. "QQ+" or "A5s-A2s"
+fn parse_part(part: &str) -> Result<Vec<[Card; 2]>, ParseRangeError> {
+    let invalid = || ParseRangeError::InvalidPart(part.to_string());
+
+    // A specific combo such as "AhKh"
+    if let Ok(cards) = parse_cards(part)
+        && let [first, second] = cards[..]
+    {
+        return Ok(vec![[first, second]]);
+    }
+
+    let shapes: Vec<Shape> = if let Some(start) = part.strip_suffix('+') {
+        let start = parse_shape(start).ok_or_else(invalid)?;
+        match start {
+            Shape::Pair(rank) => ranks_between(rank, Rank::Ace).into_iter().map(Shape::Pair).collect(),
+            _ => {
+                let (high, low) = shape_ranks(start);
+                let top_kicker = Rank::from_index(high.index() - 1).ok_or_else(invalid)?;
+                ranks_between(low, top_kicker).into_iter().map(|kicker| with_ranks(start, high, kicker)).collect()
+            }
+        }
+    } else if let Some((first, last)) = part.split_once('-') {
+        let first = parse_shape(first.trim()).ok_or_else(invalid)?;
+        let last = parse_shape(last.trim()).ok_or_else(invalid)?;
+        match (first, last) {
+            (Shape::Pair(a), Shape::Pair(b)) => ranks_between(a.min(b), a.max(b)).into_iter().map(Shape::Pair).collect(),
+            _ => {
+                // Same suitedness at both ends. Either the high card stays and the kicker goes from one end to the other ("A5s-A2s"),
+                // or both ranks move together and keep the gap between them ("T9s-65s")
+                let (first_high, a) = shape_ranks(first);
+                let (last_high, b) = shape_ranks(last);
+                if with_ranks(first, last_high, b) != last {
+                    return Err(invalid());
+                }
+                let gap = first_high.index() - a.index();
+                if first_high == last_high {
+                    ranks_between(a.min(b), a.max(b)).into_iter().map(|kicker| with_ranks(first, first_high, kicker)).collect()
+                } else if last_high.index() - b.index() == gap {
+                    ranks_between(a.min(b), a.max(b))
+                        .into_iter()
+                        .filter_map(|low| Some(with_ranks(first, Rank::from_index(low.index() + gap)?, low)))
+                        .collect()
+                } else {
+                    return Err(invalid());
+                }
+            }
+        }
+    } else {
+        vec![parse_shape(part).ok_or_else(invalid)?]
+    };
+
+    Ok(shapes.into_iter().flat_map(combos_of).collect())
+}
+
+// A shape such as "QQ", "AKs", "AKo" or "AK". None if it isn't one
+fn parse_shape(s: &str) -> Option<Shape> {
+    let mut chars = s.chars();
+    let first = Rank::from_char(chars.next()?)?;
+    let second = Rank::from_char(chars.next()?)?;
+    let suitedness = chars.next();
+    if chars.next().is_some() {
+        return None;
+    }
+
+    let (high, low) = (first.max(second), first.min(second));
+    match (high == low, suitedness.map(|c| c.to_ascii_lowercase())) {
+        (true, None) => Some(Shape::Pair(high)),
+        (false, Some('s')) => Some(Shape::Suited(high, low)),
+        (false, Some('o')) => Some(Shape::Offsuit(high, low)),
+        (false, None) => Some(Shape::Any(high, low)),
+        _ => None,
+    }
+}
+
+// The high and low rank of a shape, the same rank twice for a pair
+fn shape_ranks(shape: Shape) -> (Rank, Rank) {
+    match shape {
+        Shape::Pair(rank) => (rank, rank),
+        Shape::Suited(high, low) | Shape::Offsuit(high, low) | Shape::Any(high, low) => (high, low),
+    }
+}
+
+// A shape of the same kind with other ranks
+fn with_ranks(shape: Shape, high: Rank, low: Rank) -> Shape {
+    match shape {
+        Shape::Pair(_) => Shape::Pair(high),
+        Shape::Suited(_, _) => Shape::Suited(high, low),
+        Shape::Offsuit(_, _) => Shape::Offsuit(high, low),
+        Shape::Any(_, _) => Shape::Any(high, low),
+    }
+}
+
+// Every rank from 'low' to 'high', both included
+fn ranks_between(low: Rank, high: Rank) -> Vec<Rank> {
+    Rank::iter().filter(|rank| *rank >= low && *rank <= high).collect()
+}
+
+// Every combo of a shape, 6 for a pair, 4 suited, 12 offsuit
+fn combos_of(shape: Shape) -> Vec<[Card; 2]> {
+    let (high, low) = shape_ranks(shape);
+    let mut combos = Vec::new();
+    for first_suit in Suit::iter() {
+        for second_suit in Suit::iter() {
+            let suited = first_suit == second_suit;
+            let keep = match shape {
+                Shape::Pair(_) => first_suit < second_suit,
+                Shape::Suited(_, _) => suited,
+                Shape::Offsuit(_, _) => !suited,
+                Shape::Any(_, _) => true,
+            };
+            if keep {
+                combos.push([Card { suit: first_suit, rank: high }, Card { suit: second_suit, rank: low }]);
+            }
+        }
+    }
+    combos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Range {
+        s.parse().unwrap()
+    }
+
+    // The combos of a range written out as text and sorted, e.g. "AhKh", to compare ranges made in another order
+    fn combos(s: &str) -> Vec<String> {
+        let mut combos: Vec<String> = parse(s).combos.iter().map(|combo| format!("{}{}", combo.cards[0], combo.cards[1])).collect();
+        combos.sort();
+        combos
+    }
+
+    #[test]
+    fn combo_counts() {
+        assert_eq!(parse("QQ+, AKs, A5s-A2s").len(), 38);
+        assert_eq!(parse("AK").len(), 16);
+        assert_eq!(parse("AKs").len(), 4);
+        assert_eq!(parse("AKo").len(), 12);
+        assert_eq!(parse("QQ").len(), 6);
+        assert_eq!(parse("99-66").len(), 24);
+        assert_eq!(combos("66-99"), combos("99-66"));
+        assert_eq!(combos("ATs+"), combos("AKs, AQs, AJs, ATs"));
+        assert_eq!(parse("AhKh").len(), 1);
+        assert_eq!(parse("").len(), 0);
+    }
+
+    #[test]
+    fn suited_connectors() {
+        assert_eq!(parse("T9s-65s").len(), 20);
+        assert_eq!(combos("T9s-65s"), combos("T9s, 98s, 87s, 76s, 65s"));
+        assert_eq!(combos("65s-T9s"), combos("T9s-65s"));
+        assert_eq!(combos("KTo-85o"), combos("KTo, Q9o, J8o, T7o, 96o, 85o"));
+        assert_eq!(combos("AK-54"), combos("AK, KQ, QJ, JT, T9, 98, 87, 76, 65, 54"));
+    }
+
+    #[test]
+    fn invalid_parts() {
+        for part in ["T9s-64s", "T9s-65o", "T9s-65", "QQ-AK", "AKx", "AKs+-", "A", "AKsX", "XX", "AhAh"] {
+            assert_eq!(part.parse::<Range>(), Err(ParseRangeError::InvalidPart(part.to_string())), "{}", part);
+        }
+    }
+
+    #[test]
+    fn weights() {
+        assert_eq!(parse("KQo:0.5").weighted_len(), 6.0);
+        assert_eq!(parse("KQo:1").weighted_len(), 12.0);
+        assert_eq!(parse("QQ+:0.5, AKs").weighted_len(), 13.0);
+
+        // The last weight of a combo is used
+        let range = parse("AKs, AhKh:0.25");
+        assert_eq!(range.len(), 4);
+        assert_eq!(range.weighted_len(), 3.25);
+        assert_eq!(combos("AhKh:0.25"), vec!["AhKh"]);
+
+        for weight in ["0", "-0.5", "1.5", "abc", "", "NaN"] {
+            let range = format!("KQo:{}", weight);
+            assert_eq!(range.parse::<Range>(), Err(ParseRangeError::InvalidWeight(weight.to_string())), "{}", range);
+        }
+    }
+
+    #[test]
+    fn card_removal() {
+        let range = parse("AA, AKs");
+        let dead = CardSet::from(&parse_cards("Ah").unwrap()[..]);
+        let without = range.without(dead);
+        assert_eq!(without.len(), 3 + 3);
+        assert!(without.combos.iter().all(|combo| !combo.card_set().contains(parse_cards("Ah").unwrap()[0])));
+    }
+}
@@ -0,0 +1,64 @@
+use super::board::Board;
+use super::card::Card;
+use super::enums::{Action, Street};
+use super::hand::{Hand, HandStrength};
+use super::legal_actions::LegalActions;
+use super::player::Player;
+use super::pot::Pot;
+use serde::{Deserialize, Serialize};
+
+// An action a player took this hand, kept in Game.action_history
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub player: usize,
+    pub street: Street,
+    pub action: Action,
+}
+
+/// The game as one player is allowed to see it, made with Game::view_for.
+///
+/// # Behavior
+/// - Only the viewing player's hole cards are in 'players', everyone else has an empty hand.
+/// - At a showdown the hands of everyone that didn't fold are shown. A player that wins because everyone else folded doesn't show.
+/// - The deck and the seed it was shuffled with are never included, they would give away the cards to come.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub seat: usize, // Index of the viewing player
+    pub street: Street,
+    pub button: usize,
+    pub current_player: Option<usize>, // None when the hand is over
+    pub board: Board,
+    pub players: Vec<Player>,
+    pub pot: Pot,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub legal_actions: LegalActions, // What the viewing player can do, nothing if it isn't their turn
+    pub action_history: Vec<ActionRecord>,
+    pub showdown_winners: Vec<usize>,
+    pub showdown_hands: Vec<Option<HandStrength>>, // None for players whose hands aren't shown
+}
+
+// A hand shown at a showdown, made with Game::shown_hands
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShowdownHand {
+    pub seat: usize,
+    pub hand: Hand,
+    pub strength: HandStrength,
+}
+
+impl PlayerView {
+    // The viewing player's own hole cards, empty if they aren't dealt in
+    pub fn hole_cards(&self) -> &[Card] {
+        &self.players[self.seat].hand.cards
+    }
+
+    // True when it's the viewing player's turn
+    pub fn is_my_turn(&self) -> bool {
+        self.current_player == Some(self.seat)
+    }
+
+    pub fn hand_over(&self) -> bool {
+        self.street == Street::Showdown
+    }
+}
@@ -0,0 +1,72 @@
+// Checks the in-crate evaluator against poker_eval, which the game used before it got its own evaluator
+// poker_eval: https://docs.rs/poker_eval/latest/poker_eval/
+
+use ghaggs_joelsi_project::structs::card::Card;
+use ghaggs_joelsi_project::structs::deck::Deck;
+use ghaggs_joelsi_project::structs::evaluator::Evaluator;
+use itertools::Itertools;
+use poker_eval::eval::five::{build_tables as build_tables_five, get_rank_five};
+use poker_eval::eval::seven::{build_tables as build_tables_seven, get_rank};
+
+const RANDOM_HANDS: u64 = 20_000;
+
+fn indexes<const N: usize>(cards: &[Card]) -> [usize; N] {
+    let indexes: Vec<usize> = cards.iter().map(|card| card.as_index()).collect();
+    indexes.try_into().unwrap()
+}
+
+// Deals 'size' cards from a deck shuffled with 'seed'
+fn random_hand(seed: u64, size: usize) -> Vec<Card> {
+    let mut deck = Deck::new();
+    deck.shuffle_seeded(seed);
+    deck.draw(size).unwrap()
+}
+
+#[test]
+fn every_five_card_hand_matches_get_rank_five() {
+    let evaluator = Evaluator::new();
+    let t5 = build_tables_five(false);
+    let deck: Vec<Card> = (0..52).filter_map(Card::from_index).collect();
+
+    let mut hands = 0;
+    for five in deck.into_iter().combinations(5) {
+        let (rank, _) = evaluator.evaluate(&five).unwrap();
+        assert_eq!(rank, get_rank_five(&t5, indexes(&five)), "{:?}", five);
+        hands += 1;
+    }
+    assert_eq!(hands, 2_598_960);
+}
+
+#[test]
+fn random_six_card_hands_match_the_best_get_rank_five() {
+    let evaluator = Evaluator::new();
+    let t5 = build_tables_five(false);
+
+    for seed in 0..RANDOM_HANDS {
+        let six = random_hand(seed, 6);
+        let expected = six
+            .iter()
+            .copied()
+            .combinations(5)
+            .map(|five| get_rank_five(&t5, indexes(&five)))
+            .max()
+            .unwrap();
+        let (rank, best_five) = evaluator.evaluate(&six).unwrap();
+        assert_eq!(rank, expected, "{:?}", six);
+        assert_eq!(get_rank_five(&t5, indexes(&best_five)), rank, "{:?}", six);
+    }
+}
+
+#[test]
+fn random_seven_card_hands_match_get_rank() {
+    let evaluator = Evaluator::new();
+    let t5 = build_tables_five(false);
+    let t7 = build_tables_seven(false);
+
+    for seed in 0..RANDOM_HANDS {
+        let seven = random_hand(seed, 7);
+        let (rank, best_five) = evaluator.evaluate(&seven).unwrap();
+        assert_eq!(rank, get_rank(&t7, indexes(&seven)), "{:?}", seven);
+        assert_eq!(get_rank_five(&t5, indexes(&best_five)), rank, "{:?}", seven);
+    }
+}